@@ -0,0 +1,156 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, Monad, M, parser};
+use parsec::atom::{eq, none_of, one, pack};
+use parsec::combinator::{Either, either, try, many, sep_by1};
+use parsec::text::newline;
+use std::sync::Arc;
+
+// Dialect of CSV. Without escape char a quote in quoted field is written twice as RFC 4180,
+// with it the escape char take the next char literally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvConfig {
+    pub delimiter: char,
+    pub quote: char,
+    pub escape: Option<char>,
+    pub has_header: bool,
+}
+
+impl CsvConfig {
+    pub fn new() -> CsvConfig {
+        CsvConfig{delimiter:',', quote:'"', escape:None, has_header:false}
+    }
+
+    pub fn tsv() -> CsvConfig {
+        CsvConfig::new().delimiter('\t')
+    }
+
+    pub fn delimiter(mut self, delimiter:char) -> CsvConfig {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn quote(mut self, quote:char) -> CsvConfig {
+        self.quote = quote;
+        self
+    }
+
+    pub fn escape(mut self, escape:char) -> CsvConfig {
+        self.escape = Some(escape);
+        self
+    }
+
+    pub fn header(mut self, has_header:bool) -> CsvConfig {
+        self.has_header = has_header;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Csv {
+    pub headers: Option<Vec<String>>,
+    pub records: Vec<Vec<String>>,
+}
+
+fn chars_to_string(p:Arc<Parsec<char, Vec<char>>>) -> Monad<char, Vec<char>, String> {
+    parser(p).bind(bnd!(|_:&mut State<char>, x:Vec<char>|->Status<String> {
+        Ok(x.into_iter().collect::<String>())
+    }))
+}
+
+pub fn field(config:&CsvConfig) -> Either<char, String> {
+    let q = config.quote;
+    let escaped:Arc<Parsec<char, char>> = match config.escape {
+        Some(e) => arc!(eq(e).then(arc!(one()))),
+        None => arc!(eq(q).then(arc!(eq(q)))),
+    };
+    let content = chars_to_string(arc!(many(arc!(either(escaped, arc!(none_of(&vec![q])))))));
+    let quoted = eq(q).then(arc!(content)).over(arc!(eq(q)));
+    let plain = chars_to_string(arc!(many(arc!(none_of(&vec![config.delimiter, q, '\r', '\n'])))));
+    either(arc!(quoted), arc!(plain))
+}
+
+// End of record is a newline in any style or end of input.
+pub fn record_end() -> Monad<char, (), ()> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<()> {
+        let pos = state.pos();
+        if try(arc!(newline())).parse(state).is_ok() {
+            return Ok(());
+        }
+        match state.next() {
+            None => Ok(()),
+            Some(c) => {
+                state.seek_to(pos);
+                let message = format!("expect delimiter or end of record at {} but got {:?}", pos, c);
+                Err(SimpleError::new(pos, message))
+            }
+        }
+    }))
+}
+
+pub fn record(config:&CsvConfig) -> Monad<char, Vec<String>, Vec<String>> {
+    sep_by1(arc!(eq(config.delimiter)), arc!(field(config))).over(arc!(record_end()))
+}
+
+// Read records one by one from state, it stop at end of input or after the first error.
+pub struct CsvReader<'a> {
+    state: &'a mut State<char>,
+    record: Monad<char, Vec<String>, Vec<String>>,
+    headers: Option<Vec<String>>,
+    done: bool,
+}
+
+impl<'a> CsvReader<'a> {
+    pub fn new(state:&'a mut State<char>, config:&CsvConfig) -> Status<CsvReader<'a>> {
+        let record = record(config);
+        let headers = if config.has_header {
+            match record.parse(state) {
+                Ok(headers) => Some(headers),
+                Err(err) => return Err(err),
+            }
+        } else {
+            None
+        };
+        Ok(CsvReader{state:state, record:record, headers:headers, done:false})
+    }
+
+    pub fn headers(&self) -> Option<&Vec<String>> {
+        self.headers.as_ref()
+    }
+
+    fn at_eof(&mut self) -> bool {
+        let pos = self.state.pos();
+        let re = self.state.next().is_none();
+        self.state.seek_to(pos);
+        re
+    }
+}
+
+impl<'a> Iterator for CsvReader<'a> {
+    type Item = Status<Vec<String>>;
+
+    fn next(&mut self) -> Option<Status<Vec<String>>> {
+        if self.done || self.at_eof() {
+            return None;
+        }
+        let re = self.record.parse(self.state);
+        if re.is_err() {
+            self.done = true;
+        }
+        Some(re)
+    }
+}
+
+pub fn parse(text:&str, config:&CsvConfig) -> Status<Csv> {
+    let mut state:VecState<char> = text.chars().collect();
+    let mut reader = match CsvReader::new(&mut state, config) {
+        Ok(reader) => reader,
+        Err(err) => return Err(err),
+    };
+    let mut records = Vec::new();
+    for re in reader.by_ref() {
+        match re {
+            Ok(record) => records.push(record),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Csv{headers:reader.headers.clone(), records:records})
+}
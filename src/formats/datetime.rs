@@ -0,0 +1,749 @@
+use parsec::{State, SimpleError, Parsec, Status, Monad, M};
+use parsec::atom::{peek, expect, scan};
+use std::ascii::AsciiExt;
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+static MONTHS: [&'static str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun",
+                                      "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+static WEEKDAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub nanosecond: u32,
+}
+
+// Offset from UTC in minutes, east is positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offset {
+    pub minutes: i32,
+}
+
+// Local date time when offset is None.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub date: Date,
+    pub time: Time,
+    pub offset: Option<Offset>,
+}
+
+// ISO 8601 duration as P1Y2M3W4DT5H6M7.5S, only seconds may have fraction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Duration {
+    pub years: u32,
+    pub months: u32,
+    pub weeks: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: f64,
+}
+
+pub fn is_leap_year(year:i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year:i32, month:u32) -> u32 {
+    match month {
+        2 => if is_leap_year(year) { 29 } else { 28 },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    // Days since 1970-01-01 of proleptic gregorian calendar.
+    pub fn days_from_epoch(&self) -> i64 {
+        let year = if self.month <= 2 { self.year as i64 - 1 } else { self.year as i64 };
+        let era = if year >= 0 { year } else { year - 399 } / 400;
+        let year_of_era = year - era * 400;
+        let month = ((self.month + 9) % 12) as i64;
+        let day_of_year = (153 * month + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    // Day of week from 0 as Monday to 6 as Sunday.
+    pub fn weekday(&self) -> u32 {
+        (((self.days_from_epoch() + 3) % 7 + 7) % 7) as u32
+    }
+}
+
+impl DateTime {
+    // Seconds since unix epoch, None for local date time.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.offset.map(|offset:Offset| {
+            let seconds = self.time.hour as i64 * 3600 + self.time.minute as i64 * 60 + self.time.second as i64;
+            self.date.days_from_epoch() * 86400 + seconds - offset.minutes as i64 * 60
+        })
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        try!(write!(formatter, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second));
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            try!(write!(formatter, ".{}", fraction.trim_right_matches('0')));
+        }
+        Ok(())
+    }
+}
+
+impl Display for Offset {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        if self.minutes == 0 {
+            write!(formatter, "Z")
+        } else {
+            let sign = if self.minutes < 0 { '-' } else { '+' };
+            let minutes = self.minutes.abs();
+            write!(formatter, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+        }
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        try!(write!(formatter, "{}T{}", self.date, self.time));
+        match self.offset {
+            Some(offset) => write!(formatter, "{}", offset),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        try!(write!(formatter, "P"));
+        let dates = [(self.years, 'Y'), (self.months, 'M'), (self.weeks, 'W'), (self.days, 'D')];
+        for &(value, designator) in dates.iter() {
+            if value > 0 {
+                try!(write!(formatter, "{}{}", value, designator));
+            }
+        }
+        let empty = dates.iter().all(|&(value, _)| value == 0);
+        if self.hours > 0 || self.minutes > 0 || self.seconds > 0.0 || empty {
+            try!(write!(formatter, "T"));
+            if self.hours > 0 {
+                try!(write!(formatter, "{}H", self.hours));
+            }
+            if self.minutes > 0 {
+                try!(write!(formatter, "{}M", self.minutes));
+            }
+            if self.seconds > 0.0 || (empty && self.hours == 0 && self.minutes == 0) {
+                try!(write!(formatter, "{}S", self.seconds));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn literal(state:&mut State<char>, c:char) -> Status<()> {
+    if peek(state) == Some(c) {
+        state.next();
+        Ok(())
+    } else {
+        Err(expect(state, format!("{:?}", c).as_str()))
+    }
+}
+
+fn skip_spaces(state:&mut State<char>) -> usize {
+    let mut count = 0;
+    while peek(state) == Some(' ') || peek(state) == Some('\t') {
+        state.next();
+        count += 1;
+    }
+    count
+}
+
+fn spaces1(state:&mut State<char>) -> Status<()> {
+    if skip_spaces(state) > 0 {
+        Ok(())
+    } else {
+        Err(expect(state, "space"))
+    }
+}
+
+fn in_range(value:u32, min:u32, max:u32, what:&str, pos:usize) -> Status<u32> {
+    if min <= value && value <= max {
+        Ok(value)
+    } else {
+        let message = format!("{} {} out of range {}..{} at {}", what, value, min, max, pos);
+        Err(SimpleError::new(pos, message))
+    }
+}
+
+// Digits from min to max count as a field.
+fn digits(state:&mut State<char>, min:usize, max:usize, what:&str) -> Status<u32> {
+    let mut re = 0;
+    let mut count = 0;
+    while count < max {
+        match peek(state).and_then(|c:char| c.to_digit(10)) {
+            Some(d) => {
+                state.next();
+                re = re * 10 + d;
+                count += 1;
+            }
+            None => break,
+        }
+    }
+    if count < min {
+        Err(expect(state, format!("{} digits of {}", min, what).as_str()))
+    } else {
+        Ok(re)
+    }
+}
+
+// Fixed width field checked in range, the error is at start of the field.
+fn field(state:&mut State<char>, width:usize, min:u32, max:u32, what:&str) -> Status<u32> {
+    let pos = state.pos();
+    digits(state, width, width, what).and_then(|value:u32| in_range(value, min, max, what, pos))
+}
+
+fn check_day(date:Date, pos:usize) -> Status<Date> {
+    in_range(date.day, 1, days_in_month(date.year, date.month), "day", pos).map(|_| date)
+}
+
+fn date(state:&mut State<char>, basic:bool) -> Status<Date> {
+    let year = match field(state, 4, 0, 9999, "year") {
+        Ok(year) => year as i32,
+        Err(err) => return Err(err),
+    };
+    let extended = peek(state) == Some('-');
+    if extended {
+        state.next();
+    } else if !basic {
+        return Err(expect(state, "'-'"));
+    }
+    let month = match field(state, 2, 1, 12, "month") {
+        Ok(month) => month,
+        Err(err) => return Err(err),
+    };
+    if extended {
+        if let Err(err) = literal(state, '-') {
+            return Err(err);
+        }
+    }
+    let pos = state.pos();
+    digits(state, 2, 2, "day").and_then(|day:u32| check_day(Date{year:year, month:month, day:day}, pos))
+}
+
+// Fraction digits as nanoseconds, digits after the ninth are dropped.
+fn fraction(state:&mut State<char>) -> Status<u32> {
+    let mut re = 0;
+    let mut count = 0;
+    while let Some(d) = peek(state).and_then(|c:char| c.to_digit(10)) {
+        state.next();
+        if count < 9 {
+            re = re * 10 + d;
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Err(expect(state, "digits of fraction"));
+    }
+    for _ in count..9 {
+        re *= 10;
+    }
+    Ok(re)
+}
+
+// Optional fraction of second after "." or ",".
+fn nanosecond(state:&mut State<char>) -> Status<u32> {
+    match peek(state) {
+        Some('.') | Some(',') => {
+            state.next();
+            fraction(state)
+        }
+        _ => Ok(0),
+    }
+}
+
+// Strict time is hh:mm:ss[.f] of RFC 3339, or ISO 8601 allows hh:mm and basic hhmm[ss].
+fn time(state:&mut State<char>, strict:bool) -> Status<Time> {
+    let hour = match field(state, 2, 0, 23, "hour") {
+        Ok(hour) => hour,
+        Err(err) => return Err(err),
+    };
+    let extended = peek(state) == Some(':');
+    if extended {
+        state.next();
+    } else if strict {
+        return Err(expect(state, "':'"));
+    }
+    let minute = match field(state, 2, 0, 59, "minute") {
+        Ok(minute) => minute,
+        Err(err) => return Err(err),
+    };
+    let has_second = if extended {
+        if peek(state) == Some(':') {
+            state.next();
+            true
+        } else {
+            false
+        }
+    } else {
+        peek(state).map_or(false, |c:char| c.is_digit(10))
+    };
+    if strict && !has_second {
+        return Err(expect(state, "':'"));
+    }
+    if !has_second {
+        return Ok(Time{hour:hour, minute:minute, second:0, nanosecond:0});
+    }
+    let second = match field(state, 2, 0, 60, "second") {
+        Ok(second) => second,
+        Err(err) => return Err(err),
+    };
+    nanosecond(state).map(|nano:u32| Time{hour:hour, minute:minute, second:second, nanosecond:nano})
+}
+
+// Strict offset is Z or +hh:mm, or ISO 8601 allows +hh and +hhmm.
+fn offset(state:&mut State<char>, strict:bool) -> Status<Offset> {
+    let negative = match peek(state) {
+        Some('Z') | Some('z') => {
+            state.next();
+            return Ok(Offset{minutes:0});
+        }
+        Some('+') => false,
+        Some('-') => true,
+        _ => return Err(expect(state, "offset")),
+    };
+    state.next();
+    let hour = match field(state, 2, 0, 23, "offset hour") {
+        Ok(hour) => hour,
+        Err(err) => return Err(err),
+    };
+    let minute = if peek(state) == Some(':') {
+        state.next();
+        field(state, 2, 0, 59, "offset minute")
+    } else if strict {
+        Err(expect(state, "':'"))
+    } else if peek(state).map_or(false, |c:char| c.is_digit(10)) {
+        field(state, 2, 0, 59, "offset minute")
+    } else {
+        Ok(0)
+    };
+    minute.map(|minute:u32| {
+        let minutes = (hour * 60 + minute) as i32;
+        Offset{minutes:if negative { -minutes } else { minutes }}
+    })
+}
+
+fn date_time(state:&mut State<char>, strict:bool) -> Status<DateTime> {
+    let date = match date(state, !strict) {
+        Ok(date) => date,
+        Err(err) => return Err(err),
+    };
+    match peek(state) {
+        Some('T') | Some('t') => {
+            state.next();
+        }
+        Some(' ') if strict => {
+            state.next();
+        }
+        _ => return Err(expect(state, "'T'")),
+    }
+    let time = match time(state, strict) {
+        Ok(time) => time,
+        Err(err) => return Err(err),
+    };
+    let offset = match peek(state) {
+        Some('Z') | Some('z') | Some('+') | Some('-') => match offset(state, strict) {
+            Ok(offset) => Some(offset),
+            Err(err) => return Err(err),
+        },
+        _ if strict => return Err(expect(state, "offset")),
+        _ => None,
+    };
+    Ok(DateTime{date:date, time:time, offset:offset})
+}
+
+// Name in table without case, return its index.
+fn name(state:&mut State<char>, names:&[&'static str], what:&str) -> Status<usize> {
+    let pos = state.pos();
+    let mut word = String::new();
+    while word.len() < 3 && peek(state).map_or(false, |c:char| c.is_alphabetic()) {
+        word.push(state.next().unwrap());
+    }
+    match names.iter().position(|n:&&str| n.eq_ignore_ascii_case(word.as_str())) {
+        Some(idx) => Ok(idx),
+        None => {
+            state.seek_to(pos);
+            Err(expect(state, what))
+        }
+    }
+}
+
+fn weekday_of(date:Date, weekday:usize, pos:usize) -> Status<Date> {
+    if date.weekday() as usize == weekday {
+        Ok(date)
+    } else {
+        let message = format!("{} is {} but got {} at {}", date, WEEKDAYS[date.weekday() as usize],
+                              WEEKDAYS[weekday], pos);
+        Err(SimpleError::new(pos, message))
+    }
+}
+
+fn rfc2822_zone(state:&mut State<char>) -> Status<Offset> {
+    match peek(state) {
+        Some('+') | Some('-') => {
+            let negative = state.next() == Some('-');
+            let hour = match field(state, 2, 0, 99, "zone hour") {
+                Ok(hour) => hour,
+                Err(err) => return Err(err),
+            };
+            field(state, 2, 0, 59, "zone minute").map(|minute:u32| {
+                let minutes = (hour * 60 + minute) as i32;
+                Offset{minutes:if negative { -minutes } else { minutes }}
+            })
+        }
+        _ => {
+            let pos = state.pos();
+            let mut word = String::new();
+            while peek(state).map_or(false, |c:char| c.is_alphabetic()) {
+                word.push(state.next().unwrap());
+            }
+            let hours = match word.to_ascii_uppercase().as_str() {
+                "UT" | "GMT" | "Z" => 0,
+                "EST" => -5,
+                "EDT" => -4,
+                "CST" => -6,
+                "CDT" => -5,
+                "MST" => -7,
+                "MDT" => -6,
+                "PST" => -8,
+                "PDT" => -7,
+                _ => {
+                    state.seek_to(pos);
+                    return Err(expect(state, "zone"));
+                }
+            };
+            Ok(Offset{minutes:hours * 60})
+        }
+    }
+}
+
+fn rfc2822_date_time(state:&mut State<char>) -> Status<DateTime> {
+    skip_spaces(state);
+    let weekday_pos = state.pos();
+    let weekday = if peek(state).map_or(false, |c:char| c.is_alphabetic()) {
+        match name(state, &WEEKDAYS, "day name") {
+            Ok(weekday) => {
+                if let Err(err) = literal(state, ',') {
+                    return Err(err);
+                }
+                skip_spaces(state);
+                Some(weekday)
+            }
+            Err(err) => return Err(err),
+        }
+    } else {
+        None
+    };
+    let day_pos = state.pos();
+    let day = match digits(state, 1, 2, "day") {
+        Ok(day) => day,
+        Err(err) => return Err(err),
+    };
+    let month = match spaces1(state).and_then(|_| name(state, &MONTHS, "month name")) {
+        Ok(month) => month as u32 + 1,
+        Err(err) => return Err(err),
+    };
+    let year = match spaces1(state).and_then(|_| field(state, 4, 0, 9999, "year")) {
+        Ok(year) => year as i32,
+        Err(err) => return Err(err),
+    };
+    let date = match check_day(Date{year:year, month:month, day:day}, day_pos) {
+        Ok(date) => date,
+        Err(err) => return Err(err),
+    };
+    if let Some(weekday) = weekday {
+        if let Err(err) = weekday_of(date, weekday, weekday_pos) {
+            return Err(err);
+        }
+    }
+    let hour = match spaces1(state).and_then(|_| field(state, 2, 0, 23, "hour")) {
+        Ok(hour) => hour,
+        Err(err) => return Err(err),
+    };
+    let minute = match literal(state, ':').and_then(|_| field(state, 2, 0, 59, "minute")) {
+        Ok(minute) => minute,
+        Err(err) => return Err(err),
+    };
+    let second = if peek(state) == Some(':') {
+        state.next();
+        match field(state, 2, 0, 60, "second") {
+            Ok(second) => second,
+            Err(err) => return Err(err),
+        }
+    } else {
+        0
+    };
+    let time = Time{hour:hour, minute:minute, second:second, nanosecond:0};
+    spaces1(state).and_then(|_| rfc2822_zone(state))
+        .map(|offset:Offset| DateTime{date:date, time:time, offset:Some(offset)})
+}
+
+fn duration(state:&mut State<char>) -> Status<Duration> {
+    if let Err(err) = literal(state, 'P') {
+        return Err(err);
+    }
+    let mut re = Duration{years:0, months:0, weeks:0, days:0, hours:0, minutes:0, seconds:0.0};
+    let mut in_time = false;
+    let mut next = 0;
+    let mut count = 0;
+    loop {
+        if !in_time && peek(state) == Some('T') {
+            state.next();
+            in_time = true;
+            next = 4;
+            if !peek(state).map_or(false, |c:char| c.is_digit(10)) {
+                return Err(expect(state, "time component"));
+            }
+            continue;
+        }
+        if !peek(state).map_or(false, |c:char| c.is_digit(10)) {
+            break;
+        }
+        let value = match digits(state, 1, 9, "duration") {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+        let fraction_pos = state.pos();
+        let fraction = match nanosecond(state) {
+            Ok(nano) => nano,
+            Err(err) => return Err(err),
+        };
+        let pos = state.pos();
+        let designators = if in_time { "HMS" } else { "YMWD" };
+        let offset = if in_time { 4 } else { 0 };
+        let idx = match peek(state).and_then(|c:char| designators.find(c)) {
+            Some(idx) => idx + offset,
+            None => return Err(expect(state, "designator")),
+        };
+        if idx < next {
+            let message = format!("duration designator out of order at {}", pos);
+            return Err(SimpleError::new(pos, message));
+        }
+        if pos != fraction_pos && idx != 6 {
+            let message = format!("fraction only allowed in seconds at {}", fraction_pos);
+            return Err(SimpleError::new(fraction_pos, message));
+        }
+        state.next();
+        match idx {
+            0 => re.years = value,
+            1 => re.months = value,
+            2 => re.weeks = value,
+            3 => re.days = value,
+            4 => re.hours = value,
+            5 => re.minutes = value,
+            _ => re.seconds = value as f64 + fraction as f64 / 1e9,
+        }
+        next = idx + 1;
+        count += 1;
+    }
+    if count == 0 {
+        Err(expect(state, "duration component"))
+    } else {
+        Ok(re)
+    }
+}
+
+// YYYY-MM-DD or basic YYYYMMDD.
+pub fn iso_date() -> Monad<char, (), Date> {
+    scan(bnd!(|state: &mut State<char>|->Status<Date> { date(state, true) }))
+}
+
+// hh:mm[:ss[.fraction]] or basic hhmm[ss[.fraction]], fraction may be separated by ",".
+pub fn iso_time() -> Monad<char, (), Time> {
+    scan(bnd!(|state: &mut State<char>|->Status<Time> { time(state, false) }))
+}
+
+// Z, +hh, +hhmm or +hh:mm.
+pub fn iso_offset() -> Monad<char, (), Offset> {
+    scan(bnd!(|state: &mut State<char>|->Status<Offset> { offset(state, false) }))
+}
+
+// Date and time separated by "T" with an optional offset.
+pub fn iso_datetime() -> Monad<char, (), DateTime> {
+    scan(bnd!(|state: &mut State<char>|->Status<DateTime> { date_time(state, false) }))
+}
+
+pub fn iso_duration() -> Monad<char, (), Duration> {
+    scan(bnd!(|state: &mut State<char>|->Status<Duration> { duration(state) }))
+}
+
+// Internet timestamp as 1985-04-12T23:20:50.52Z, seconds and offset are required, the
+// separator may be "T", "t" or a space.
+pub fn rfc3339() -> Monad<char, (), DateTime> {
+    scan(bnd!(|state: &mut State<char>|->Status<DateTime> { date_time(state, true) }))
+}
+
+// Mail date as "Fri, 21 Nov 1997 09:55:06 -0600", the day name is checked if given.
+pub fn rfc2822() -> Monad<char, (), DateTime> {
+    scan(bnd!(|state: &mut State<char>|->Status<DateTime> { rfc2822_date_time(state) }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    Literal(char),
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Fraction,
+    Zone,
+    MonthName,
+    WeekdayName,
+}
+
+// Parser built from a strftime like format. %Y is four digits, %m %d %H %M %S are two digits,
+// %f is fraction digits, %z is an offset as Z, +hh:mm or +hhmm, %b and %a are english names
+// of month and day, %% is "%". Other chars match themselves. Missing fields default to
+// 1970-01-01 00:00:00 without offset.
+pub struct DateFormat {
+    source: Arc<String>,
+    directives: Arc<Vec<Directive>>,
+}
+
+impl DateFormat {
+    pub fn new(format:&str) -> Result<DateFormat, String> {
+        let mut directives = Vec::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                directives.push(Directive::Literal(c));
+                continue;
+            }
+            let directive = match chars.next() {
+                Some('Y') => Directive::Year,
+                Some('m') => Directive::Month,
+                Some('d') => Directive::Day,
+                Some('H') => Directive::Hour,
+                Some('M') => Directive::Minute,
+                Some('S') => Directive::Second,
+                Some('f') => Directive::Fraction,
+                Some('z') => Directive::Zone,
+                Some('b') => Directive::MonthName,
+                Some('a') => Directive::WeekdayName,
+                Some('%') => Directive::Literal('%'),
+                Some(x) => return Err(format!("unknown directive %{} in format {:?}", x, format)),
+                None => return Err(format!("incomplete directive at end of format {:?}", format)),
+            };
+            directives.push(directive);
+        }
+        Ok(DateFormat{source:Arc::new(String::from(format)), directives:Arc::new(directives)})
+    }
+}
+
+impl Parsec<char, DateTime> for DateFormat {
+    fn parse(&self, state:&mut State<char>)->Status<DateTime> {
+        let start = state.pos();
+        let mut date = Date{year:1970, month:1, day:1};
+        let mut time = Time{hour:0, minute:0, second:0, nanosecond:0};
+        let mut zone = None;
+        let mut day_pos = None;
+        let mut weekday = None;
+        for directive in self.directives.iter() {
+            let pos = state.pos();
+            let re = match *directive {
+                Directive::Literal(c) => literal(state, c),
+                Directive::Year => field(state, 4, 0, 9999, "year").map(|x:u32| date.year = x as i32),
+                Directive::Month => field(state, 2, 1, 12, "month").map(|x:u32| date.month = x),
+                Directive::Day => {
+                    day_pos = Some(pos);
+                    field(state, 2, 1, 31, "day").map(|x:u32| date.day = x)
+                }
+                Directive::Hour => field(state, 2, 0, 23, "hour").map(|x:u32| time.hour = x),
+                Directive::Minute => field(state, 2, 0, 59, "minute").map(|x:u32| time.minute = x),
+                Directive::Second => field(state, 2, 0, 60, "second").map(|x:u32| time.second = x),
+                Directive::Fraction => fraction(state).map(|x:u32| time.nanosecond = x),
+                Directive::Zone => offset(state, false).map(|x:Offset| zone = Some(x)),
+                Directive::MonthName => name(state, &MONTHS, "month name").map(|x:usize| date.month = x as u32 + 1),
+                Directive::WeekdayName => name(state, &WEEKDAYS, "day name").map(|x:usize| weekday = Some((x, pos))),
+            };
+            if let Err(err) = re {
+                state.seek_to(start);
+                return Err(err);
+            }
+        }
+        let checked = check_day(date, day_pos.unwrap_or(start)).and_then(|date:Date| match weekday {
+            Some((weekday, pos)) => weekday_of(date, weekday, pos),
+            None => Ok(date),
+        });
+        match checked {
+            Ok(date) => Ok(DateTime{date:date, time:time, offset:zone}),
+            Err(err) => {
+                state.seek_to(start);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<'a> FnOnce<(&'a mut State<char>, )> for DateFormat {
+    type Output = Status<DateTime>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<char>, )) -> Status<DateTime> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> FnMut<(&'a mut State<char>, )> for DateFormat {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<char>, )) -> Status<DateTime> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> Fn<(&'a mut State<char>, )> for DateFormat {
+    extern "rust-call" fn call(&self, args: (&'a mut State<char>, )) -> Status<DateTime> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl Clone for DateFormat {
+    fn clone(&self)->Self {
+        DateFormat{source:self.source.clone(), directives:self.directives.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.source = source.source.clone();
+        self.directives = source.directives.clone();
+    }
+}
+
+impl Debug for DateFormat {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "<date format {:?}>", self.source)
+    }
+}
+
+impl M<char, DateTime> for DateFormat{}
+
+// Panic if the format is invalid, use DateFormat::new to check it.
+pub fn strptime(format:&str) -> DateFormat {
+    match DateFormat::new(format) {
+        Ok(p) => p,
+        Err(message) => panic!("{}", message),
+    }
+}
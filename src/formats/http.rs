@@ -0,0 +1,308 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, Monad, M, parser};
+use parsec::atom::{Satisfy, one, pack, satisfy};
+use parsec::combinator::{many, many1, optional, skip_many};
+use std::ascii::AsciiExt;
+use std::sync::Arc;
+
+// Result of parsing a buffer which may not be received completely. Complete carries the value
+// and count of bytes consumed, Incomplete means the input ends before the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Parsed<T> {
+    Complete(T, usize),
+    Incomplete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub version: (u8, u8),
+    pub headers: Vec<Header>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub version: (u8, u8),
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<Header>,
+}
+
+// Value of the first header named as name without case.
+fn find_header<'a>(headers:&'a Vec<Header>, name:&str) -> Option<&'a str> {
+    headers.iter().find(|h:&&Header| h.name.eq_ignore_ascii_case(name)).map(|h:&Header| h.value.as_str())
+}
+
+impl Request {
+    pub fn header(&self, name:&str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl Response {
+    pub fn header(&self, name:&str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+// Byte b, errors name it as a char.
+fn byte(b:u8) -> Satisfy<u8> {
+    satisfy(bnd!(move |x:&u8| *x == b), format!("{:?}", b as char))
+}
+
+// The bytes of text in order, an error is at the first byte mismatch.
+fn bytes(text:&'static [u8]) -> Monad<u8, (), ()> {
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<()> {
+        for b in text.iter() {
+            try!(byte(*b).parse(state));
+        }
+        Ok(())
+    }))
+}
+
+fn digit() -> Satisfy<u8> {
+    satisfy(bnd!(|b:&u8| (*b as char).is_digit(10)), String::from("digit"))
+}
+
+fn is_tchar(b:u8) -> bool {
+    (b < 0x80 && (b as char).is_alphanumeric()) || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+pub fn token() -> Satisfy<u8> {
+    satisfy(bnd!(|b:&u8| is_tchar(*b)), String::from("token char"))
+}
+
+fn text(bytes:Vec<u8>) -> String {
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn token_text() -> Monad<u8, Vec<u8>, String> {
+    parser(arc!(many1(arc!(token())))).bind(bnd!(|_:&mut State<u8>, x:Vec<u8>|->Status<String> {
+        Ok(text(x))
+    }))
+}
+
+// CRLF, or a bare LF as RFC 7230 section 3.5 allows.
+fn line_end() -> Monad<u8, Option<u8>, u8> {
+    optional(arc!(byte(b'\r'))).then(arc!(byte(b'\n')))
+}
+
+fn version() -> Monad<u8, u8, (u8, u8)> {
+    bytes(b"HTTP/").then(arc!(digit())).over(arc!(byte(b'.'))).bind(bnd!(|state:&mut State<u8>, major:u8|->Status<(u8, u8)> {
+        digit().parse(state).map(|minor:u8| (major - b'0', minor - b'0'))
+    }))
+}
+
+// A byte of field value or reason phrase, control bytes are not allowed.
+fn field_byte() -> Satisfy<u8> {
+    satisfy(bnd!(|b:&u8| *b == b'\t' || (*b >= 0x20 && *b != 0x7f)), String::from("field value"))
+}
+
+fn trim(mut value:Vec<u8>) -> Vec<u8> {
+    while value.last().map_or(false, |b:&u8| *b == b' ' || *b == b'\t') {
+        value.pop();
+    }
+    let start = value.iter().position(|b:&u8| *b != b' ' && *b != b'\t').unwrap_or(value.len());
+    value.split_off(start)
+}
+
+// Field value to the line end, spaces around it are trimmed.
+fn field_value() -> Monad<u8, Vec<u8>, String> {
+    parser(arc!(many(arc!(field_byte())))).over(arc!(line_end())).bind(bnd!(|_:&mut State<u8>, x:Vec<u8>|->Status<String> {
+        Ok(text(trim(x)))
+    }))
+}
+
+fn header_line() -> Monad<u8, String, Header> {
+    let value = field_value();
+    token_text().over(arc!(byte(b':'))).bind(bnd!(move |state:&mut State<u8>, name:String|->Status<Header> {
+        value.parse(state).map(|value:String| Header{name:name, value:value})
+    }))
+}
+
+// Headers until an empty line. A line starts with space or tab is folded into the value of
+// previous header with a single space.
+fn header_lines() -> Monad<u8, (), Vec<Header>> {
+    let end = line_end();
+    let fold = optional(arc!(many1(arc!(satisfy(bnd!(|b:&u8| *b == b' ' || *b == b'\t'), String::from("space"))))));
+    let line = header_line();
+    let value = field_value();
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<Vec<Header>> {
+        let mut re:Vec<Header> = Vec::new();
+        loop {
+            let pos = state.pos();
+            if end.parse(state).is_ok() {
+                return Ok(re);
+            }
+            state.seek_to(pos);
+            if try!(fold.parse(state)).is_none() {
+                re.push(try!(line.parse(state)));
+                continue;
+            }
+            if re.is_empty() {
+                let message = format!("folded line without header at {}", pos);
+                return Err(SimpleError::new(pos, message));
+            }
+            let value = try!(value.parse(state));
+            let last = re.last_mut().unwrap();
+            if !value.is_empty() {
+                if !last.value.is_empty() {
+                    last.value.push(' ');
+                }
+                last.value.push_str(value.as_str());
+            }
+        }
+    }))
+}
+
+// Request line and headers, empty lines before the request line are skipped.
+pub fn request_head() -> Monad<u8, (), Request> {
+    let empty_lines = skip_many(arc!(line_end()));
+    let method = token_text();
+    let target = parser(arc!(many1(arc!(satisfy(bnd!(|b:&u8| *b > 0x20 && *b != 0x7f), String::from("request target"))))))
+        .bind(bnd!(|_:&mut State<u8>, x:Vec<u8>|->Status<String> { Ok(text(x)) }));
+    let space = byte(b' ');
+    let version = version();
+    let end = line_end();
+    let headers = header_lines();
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<Request> {
+        try!(empty_lines.parse(state));
+        let method = try!(method.parse(state));
+        try!(space.parse(state));
+        let target = try!(target.parse(state));
+        try!(space.parse(state));
+        let version = try!(version.parse(state));
+        try!(end.parse(state));
+        let headers = try!(headers.parse(state));
+        Ok(Request{method:method, target:target, version:version, headers:headers})
+    }))
+}
+
+// Status line and headers.
+pub fn response_head() -> Monad<u8, (), Response> {
+    let version = version().over(arc!(byte(b' ')));
+    let status_digit = satisfy(bnd!(|b:&u8| (*b as char).is_digit(10)), String::from("status code digit"));
+    let reason = optional(arc!(byte(b' ').then(arc!(many(arc!(field_byte()))))));
+    let end = line_end();
+    let headers = header_lines();
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<Response> {
+        let version = try!(version.parse(state));
+        let mut status = 0;
+        for _ in 0..3 {
+            status = status * 10 + (try!(status_digit.parse(state)) - b'0') as u16;
+        }
+        let reason = try!(reason.parse(state)).map_or(String::new(), text);
+        try!(end.parse(state));
+        let headers = try!(headers.parse(state));
+        Ok(Response{version:version, status:status, reason:reason, headers:headers})
+    }))
+}
+
+// Header lines and the empty line after them.
+pub fn headers() -> Monad<u8, (), Vec<Header>> {
+    header_lines()
+}
+
+// Hex size line of a chunk, chunk extensions are ignored.
+fn chunk_size() -> Monad<u8, (), usize> {
+    let hex = many1(arc!(satisfy(bnd!(|b:&u8| (*b as char).is_digit(16)), String::from("chunk size"))));
+    let extension = skip_many(arc!(satisfy(bnd!(|b:&u8| *b != b'\r' && *b != b'\n'), String::from("chunk extension"))));
+    let end = line_end();
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<usize> {
+        let start = state.pos();
+        let digits = try!(hex.parse(state));
+        let mut size:usize = 0;
+        for d in digits.iter().map(|b:&u8| (*b as char).to_digit(16).unwrap()) {
+            match size.checked_mul(16).and_then(|x:usize| x.checked_add(d as usize)) {
+                Some(x) => size = x,
+                None => {
+                    let message = format!("chunk size at {} overflow", start);
+                    return Err(SimpleError::new(start, message));
+                }
+            }
+        }
+        try!(extension.parse(state));
+        try!(end.parse(state));
+        Ok(size)
+    }))
+}
+
+// Body in chunked transfer coding, return the decoded data and trailer headers.
+pub fn chunked_body() -> Monad<u8, (), (Vec<u8>, Vec<Header>)> {
+    let size = chunk_size();
+    let data = one();
+    let end = line_end();
+    let trailers = header_lines();
+    pack(()).bind(bnd!(move |state:&mut State<u8>, _:()|->Status<(Vec<u8>, Vec<Header>)> {
+        let mut body = Vec::new();
+        loop {
+            let size = try!(size.parse(state));
+            if size == 0 {
+                return trailers.parse(state).map(|trailers:Vec<Header>| (body, trailers));
+            }
+            for _ in 0..size {
+                body.push(try!(data.parse(state)));
+            }
+            try!(end.parse(state));
+        }
+    }))
+}
+
+// The bytes received so far. It records a read past the end, so a failed parse there needs
+// more bytes rather than has a bad one.
+struct Received {
+    state: VecState<u8>,
+    length: usize,
+    exhausted: bool,
+}
+
+impl State<u8> for Received {
+    fn pos(&self) -> usize {
+        self.state.pos()
+    }
+    fn seek_to(&mut self, to:usize) -> bool {
+        self.state.seek_to(to)
+    }
+    fn next(&mut self) -> Option<u8> {
+        let re = self.state.next();
+        if re.is_none() {
+            self.exhausted = true;
+        }
+        re
+    }
+    fn next_by(&mut self, pred:&Fn(&u8)->bool) -> Status<u8> {
+        if self.state.pos() >= self.length {
+            self.exhausted = true;
+        }
+        self.state.next_by(pred)
+    }
+}
+
+// Run parsec on the bytes received so far, it is incomplete if the parsec failed after it
+// tried to read past the end of buffer.
+pub fn parse_incremental<R>(p:&Parsec<u8, R>, buffer:&[u8]) -> Status<Parsed<R>> {
+    let mut state = Received{state:buffer.iter().cloned().collect(), length:buffer.len(), exhausted:false};
+    match p.parse(&mut state) {
+        Ok(re) => Ok(Parsed::Complete(re, state.pos())),
+        Err(_) if state.exhausted => Ok(Parsed::Incomplete),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn parse_request(buffer:&[u8]) -> Status<Parsed<Request>> {
+    parse_incremental(&request_head(), buffer)
+}
+
+pub fn parse_response(buffer:&[u8]) -> Status<Parsed<Response>> {
+    parse_incremental(&response_head(), buffer)
+}
+
+pub fn parse_chunked(buffer:&[u8]) -> Status<Parsed<(Vec<u8>, Vec<Header>)>> {
+    parse_incremental(&chunked_body(), buffer)
+}
@@ -0,0 +1,518 @@
+use parsec::{State, VecState, SimpleError, Error, Parsec, Status, Monad, M, parser};
+use parsec::atom::{Satisfy, eq, eof, pack, string, satisfy, one_of_str, none_of_str};
+use parsec::combinator::{Either, Skip, Try, either, try, many, many1, many_m_n, between, sep_by1, optional,
+                         look_ahead, not_followed_by};
+use parsec::indent::{line, column};
+use parsec::lexeme::Lexeme;
+use parsec::lazy::lazy;
+use parsec::regex::regex;
+use parsec::text::{newline, hex_digit, int64, float64};
+use formats::datetime::{Date, Time, DateTime, rfc3339, iso_datetime, iso_date, iso_time};
+use std::sync::Arc;
+use std::char;
+
+// Values of TOML subset: strings, integers, floats, booleans, offset or local date times,
+// local dates, local times, arrays and tables. INI documents only have strings in sections.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Datetime(DateTime),
+    Date(Date),
+    Time(Time),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+// Table keep the entries in order of the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub entries: Vec<(String, Value)>,
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table{entries:Vec::new()}
+    }
+
+    pub fn get(&self, key:&str) -> Option<&Value> {
+        self.entries.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v)
+    }
+
+    // Lookup by dotted path as "server.http.port", array of tables use the last one.
+    pub fn get_path(&self, path:&str) -> Option<&Value> {
+        let mut keys = path.split('.');
+        let mut current = match keys.next().and_then(|key:&str| self.get(key)) {
+            Some(value) => value,
+            None => return None,
+        };
+        for key in keys {
+            current = match *current {
+                Value::Table(ref table) => match table.get(key) {
+                    Some(value) => value,
+                    None => return None,
+                },
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    fn position(&self, key:&str) -> Option<usize> {
+        self.entries.iter().position(|&(ref k, _)| k == key)
+    }
+}
+
+fn error_at(state:&mut State<char>, pos:usize, message:String) -> SimpleError {
+    let message = format!("{} at line {} column {}", message, line(state, pos), column(state, pos));
+    SimpleError::new(pos, message)
+}
+
+// Run p, a failure without consuming anything is reported as "expect what" with line and
+// column. Errors after p consumed input are kept.
+fn expected<R:'static+Clone>(p:Arc<Parsec<char, R>>, what:&str) -> Monad<char, (), R> {
+    let what = String::from(what);
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<R> {
+        let pos = state.pos();
+        match p.parse(state) {
+            Err(_) if state.pos() == pos => Err(error_at(state, pos, format!("expect {}", what))),
+            re => re,
+        }
+    }))
+}
+
+// Run a literal value, any error is reported at its start with line and column.
+fn literal<R:'static+Clone>(p:Arc<Parsec<char, R>>, what:&str) -> Monad<char, (), R> {
+    let what = String::from(what);
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<R> {
+        let start = state.pos();
+        p.parse(state).or_else(|err:SimpleError| {
+            let message = format!("invalid {}: {}", what, err.message());
+            Err(error_at(state, start, message))
+        })
+    }))
+}
+
+fn skipper(white:&str, prefixes:&str) -> Skip<char, ()> {
+    prefixes.chars().fold(Lexeme::white(arc!(one_of_str(white))), |lexer:Lexeme, prefix:char| {
+        lexer.line_comment(prefix.to_string().as_str())
+    }).white_space()
+}
+
+// Spaces and a line comment starting with one of prefixes.
+fn spaces(prefixes:&str) -> Skip<char, ()> {
+    skipper(" \t", prefixes)
+}
+
+// Blank lines, spaces and comments.
+fn blank(prefixes:&str) -> Skip<char, ()> {
+    skipper(" \t\r\n", prefixes)
+}
+
+// Spaces and a comment to the end of line or input.
+fn line_end(prefixes:&str) -> Monad<char, Vec<()>, ()> {
+    let end = either(arc!(try(arc!(newline())).then(arc!(pack(())))), arc!(try(arc!(eof()))));
+    spaces(prefixes).then(arc!(expected(arc!(end), "end of line")))
+}
+
+fn at_eof(state:&mut State<char>) -> bool {
+    look_ahead(arc!(eof())).parse(state).is_ok()
+}
+
+fn insert(state:&mut State<char>, table:&mut Table, key:String, value:Value, pos:usize) -> Status<()> {
+    if table.position(key.as_str()).is_some() {
+        let message = format!("duplicate key {:?}", key);
+        return Err(error_at(state, pos, message));
+    }
+    table.entries.push((key, value));
+    Ok(())
+}
+
+// Table at path under root, missing tables are created, array of tables give the last one.
+fn table_at<'t>(root:&'t mut Table, path:&[String]) -> Result<&'t mut Table, String> {
+    if path.is_empty() {
+        return Ok(root);
+    }
+    let key = path[0].clone();
+    let idx = match root.position(key.as_str()) {
+        Some(idx) => idx,
+        None => {
+            root.entries.push((key.clone(), Value::Table(Table::new())));
+            root.entries.len() - 1
+        }
+    };
+    match root.entries[idx].1 {
+        Value::Table(ref mut table) => table_at(table, &path[1..]),
+        Value::Array(ref mut items) => match items.last_mut() {
+            Some(&mut Value::Table(ref mut table)) => table_at(table, &path[1..]),
+            _ => Err(format!("key {:?} is not a table", key)),
+        },
+        _ => Err(format!("key {:?} is not a table", key)),
+    }
+}
+
+// Put value at the dotted path under table, pos is the start of key for errors.
+fn assign(state:&mut State<char>, table:&mut Table, pos:usize, path:Vec<String>, value:Value) -> Status<()> {
+    let (last, prefix) = path.split_last().unwrap();
+    match table_at(table, prefix) {
+        Ok(target) => insert(state, target, last.clone(), value, pos),
+        Err(message) => Err(error_at(state, pos, message)),
+    }
+}
+
+fn is_bare(c:char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn bare_char() -> Satisfy<char> {
+    satisfy(bnd!(|c:&char| is_bare(*c)), String::from("bare key char"))
+}
+
+// A literal ends before a space, comma, bracket or comment, not in a word or number.
+fn literal_end() -> Monad<char, (), ()> {
+    not_followed_by(arc!(either(arc!(bare_char()), arc!(eq('.')))))
+}
+
+fn chars(p:Arc<Parsec<char, Vec<char>>>) -> Monad<char, Vec<char>, String> {
+    parser(p).bind(bnd!(|_:&mut State<char>, x:Vec<char>|->Status<String> {
+        Ok(x.into_iter().collect::<String>())
+    }))
+}
+
+// Escape in a basic string as the TOML escape table, \uXXXX and \UXXXXXXXX are unicode scalar
+// values.
+fn escape() -> Monad<char, char, char> {
+    eq('\\').bind(bnd!(|state:&mut State<char>, _:char|->Status<char> {
+        let start = state.pos() - 1;
+        let digits = match state.next() {
+            Some('b') => return Ok('\u{8}'),
+            Some('t') => return Ok('\t'),
+            Some('n') => return Ok('\n'),
+            Some('f') => return Ok('\u{c}'),
+            Some('r') => return Ok('\r'),
+            Some('"') => return Ok('"'),
+            Some('\\') => return Ok('\\'),
+            Some('u') => 4,
+            Some('U') => 8,
+            Some(c) => return Err(error_at(state, start, format!("unknown escape \\{}", c))),
+            None => return Err(error_at(state, start, String::from("unterminated escape"))),
+        };
+        let hex = many_m_n(digits, digits, arc!(hex_digit()));
+        let code = match hex.parse(state) {
+            Ok(x) => u32::from_str_radix(x.into_iter().collect::<String>().as_str(), 16).unwrap(),
+            Err(_) => {
+                let message = format!("invalid escape, expect {} hex digits", digits);
+                return Err(error_at(state, start, message));
+            }
+        };
+        char::from_u32(code).ok_or_else(|| {
+            error_at(state, start, format!("invalid unicode escape {:X}", code))
+        })
+    }))
+}
+
+// "text" in one line with escapes, control chars other than tab are not allowed.
+fn basic_string() -> Monad<char, char, String> {
+    let plain = satisfy(bnd!(|c:&char| *c != '"' && *c != '\\' && (*c == '\t' || (*c >= ' ' && *c != '\u{7f}'))),
+                        String::from("string char"));
+    let item = either(arc!(escape()), arc!(plain));
+    let close = expected(arc!(eq('"')), "closing quote");
+    eq('"').bind(bnd!(move |state:&mut State<char>, _:char|->Status<String> {
+        let mut re = String::new();
+        loop {
+            let pos = state.pos();
+            match item.parse(state) {
+                Ok(c) => re.push(c),
+                Err(err) => {
+                    if state.pos() != pos {
+                        return Err(err);
+                    }
+                    return close.parse(state).map(|_| re);
+                }
+            }
+        }
+    }))
+}
+
+// 'text' in one line without escapes.
+fn literal_string() -> Monad<char, String, String> {
+    let body = chars(arc!(many(arc!(none_of_str("'\r\n")))));
+    eq('\'').then(arc!(body)).over(arc!(expected(arc!(eq('\'')), "closing quote")))
+}
+
+fn simple_key() -> Monad<char, (), String> {
+    let bare = chars(arc!(many1(arc!(bare_char()))));
+    expected(arc!(either(arc!(basic_string()), arc!(literal_string())).or(arc!(bare))), "key")
+}
+
+// Dotted key as a.b."c.d" and the spaces around it.
+fn key() -> Monad<char, Vec<String>, Vec<String>> {
+    let dot = spaces("").then(arc!(eq('.'))).then(arc!(spaces("")));
+    spaces("").then(arc!(sep_by1(arc!(dot), arc!(simple_key())))).over(arc!(spaces("")))
+}
+
+fn word(text:&str, value:bool) -> Try<char, Value> {
+    try(arc!(string(text).over(arc!(literal_end())).then(arc!(pack(Value::Boolean(value))))))
+}
+
+fn boolean() -> Either<char, Value> {
+    either(arc!(word("true", true)), arc!(word("false", false)))
+}
+
+// Offset date time as RFC 3339, local date time, local date or local time. Only the extended
+// forms are tried, basic forms as 20240101 are numbers.
+fn datetime() -> Monad<char, (), Value> {
+    let shape = look_ahead(arc!(regex(r"\d{4}-\d{2}-\d{2}|\d{2}:\d{2}:")));
+    let spaced = iso_date().over(arc!(eq(' '))).bind(bnd!(|state:&mut State<char>, date:Date|->Status<DateTime> {
+        iso_time().parse(state).map(|time:Time| DateTime{date:date, time:time, offset:None})
+    }));
+    let date_time = either(arc!(try(arc!(rfc3339()))), arc!(try(arc!(iso_datetime())))).or(arc!(try(arc!(spaced))))
+        .bind(bnd!(|_:&mut State<char>, x:DateTime|->Status<Value> { Ok(Value::Datetime(x)) }));
+    let date = try(arc!(iso_date())).bind(bnd!(|_:&mut State<char>, x:Date|->Status<Value> { Ok(Value::Date(x)) }));
+    let time = try(arc!(iso_time())).bind(bnd!(|_:&mut State<char>, x:Time|->Status<Value> { Ok(Value::Time(x)) }));
+    let value = shape.then(arc!(either(arc!(date_time), arc!(date)).or(arc!(time)))).over(arc!(literal_end()));
+    literal(arc!(value), "date time")
+}
+
+// Integer in decimal, hex, octal or binary, or float if it has a fraction, an exponent, inf or
+// nan, so an integer out of range is an error instead of a float.
+fn number() -> Monad<char, (), Value> {
+    let shape = look_ahead(arc!(regex(r"[+-]?(inf|nan|[0-9_]*\.[0-9]|[0-9_]+[eE])")));
+    let float = try(arc!(shape.then(arc!(float64())))).bind(bnd!(|_:&mut State<char>, x:f64|->Status<Value> {
+        Ok(Value::Float(x))
+    }));
+    let integer = int64().bind(bnd!(|_:&mut State<char>, x:i64|->Status<Value> { Ok(Value::Integer(x)) }));
+    literal(arc!(either(arc!(float), arc!(integer)).over(arc!(literal_end()))), "number")
+}
+
+// [a, b, ] across lines and comments, a trailing comma is allowed.
+fn array(item:Arc<Parsec<char, Value>>) -> Monad<char, Vec<Value>, Value> {
+    let gap:Arc<Parsec<char, Vec<()>>> = arc!(blank("#"));
+    let comma = arc!(eq(',').then(gap.clone()));
+    let items = either(arc!(sep_by1(comma.clone(), arc!(parser(item).over(gap.clone())))), arc!(pack(Vec::new())));
+    let body = items.over(arc!(optional(comma)));
+    between(arc!(eq('[').then(gap)), arc!(body), arc!(expected(arc!(eq(']')), "',' or ']'")))
+        .bind(bnd!(|_:&mut State<char>, items:Vec<Value>|->Status<Value> { Ok(Value::Array(items)) }))
+}
+
+// { a = 1, b.c = 2 } in one line.
+fn inline_table(pair:Arc<Parsec<char, (usize, Vec<String>, Value)>>) -> Monad<char, Vec<(usize, Vec<String>, Value)>, Value> {
+    let comma = eq(',').then(arc!(spaces("")));
+    let items = either(arc!(sep_by1(arc!(comma), pair)), arc!(pack(Vec::new())));
+    between(arc!(eq('{').then(arc!(spaces("")))), arc!(items), arc!(expected(arc!(eq('}')), "',' or '}'")))
+        .bind(bnd!(|state:&mut State<char>, pairs:Vec<(usize, Vec<String>, Value)>|->Status<Value> {
+            let mut table = Table::new();
+            for (pos, path, value) in pairs {
+                try!(assign(state, &mut table, pos, path, value));
+            }
+            Ok(Value::Table(table))
+        }))
+}
+
+fn value() -> Arc<Parsec<char, Value>> {
+    let item:Arc<Parsec<char, Value>> = arc!(lazy(value));
+    let pair:Arc<Parsec<char, (usize, Vec<String>, Value)>> = arc!(lazy(key_value));
+    let text = either(arc!(basic_string()), arc!(literal_string()))
+        .bind(bnd!(|_:&mut State<char>, x:String|->Status<Value> { Ok(Value::String(x)) }));
+    arc!(either(arc!(text), arc!(array(item)))
+        .or(arc!(inline_table(pair)))
+        .or(arc!(boolean()))
+        .or(arc!(datetime()))
+        .or(arc!(number())))
+}
+
+// key = value and the spaces after it, with the start of key for errors.
+fn key_value() -> Arc<Parsec<char, (usize, Vec<String>, Value)>> {
+    let space = spaces("");
+    let key = key();
+    let equal = expected(arc!(eq('=')), "'='");
+    let value = value();
+    arc!(pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<(usize, Vec<String>, Value)> {
+        try!(space.parse(state));
+        let start = state.pos();
+        let path = try!(key.parse(state));
+        try!(equal.parse(state));
+        try!(space.parse(state));
+        let value = try!(value.parse(state));
+        try!(space.parse(state));
+        Ok((start, path, value))
+    })))
+}
+
+// A line of document, tables and key value pairs keep the start position for errors.
+#[derive(Debug, Clone)]
+enum Line {
+    Table(usize, Vec<String>),
+    ArrayTable(usize, Vec<String>),
+    Pair(usize, Vec<String>, Value),
+}
+
+// Table header as [a.b] or array of tables as [[a.b]].
+fn header() -> Monad<char, char, Line> {
+    let double = optional(arc!(eq('[')));
+    let key = key();
+    let close_table = expected(arc!(string("]")), "']'");
+    let close_array = expected(arc!(string("]]")), "']]'");
+    eq('[').bind(bnd!(move |state:&mut State<char>, _:char|->Status<Line> {
+        let start = state.pos() - 1;
+        let is_array = try!(double.parse(state)).is_some();
+        let path = try!(key.parse(state));
+        if is_array {
+            try!(close_array.parse(state));
+            Ok(Line::ArrayTable(start, path))
+        } else {
+            try!(close_table.parse(state));
+            Ok(Line::Table(start, path))
+        }
+    }))
+}
+
+fn define_table(state:&mut State<char>, root:&mut Table, defined:&mut Vec<Vec<String>>, start:usize, path:&Vec<String>)
+        -> Status<()> {
+    if defined.contains(path) {
+        return Err(error_at(state, start, format!("table {:?} defined twice", path.join("."))));
+    }
+    defined.push(path.clone());
+    match table_at(root, &path[..]) {
+        Ok(_) => Ok(()),
+        Err(message) => Err(error_at(state, start, message)),
+    }
+}
+
+fn push_table(state:&mut State<char>, root:&mut Table, start:usize, path:&Vec<String>) -> Status<()> {
+    let (last, prefix) = path.split_last().unwrap();
+    let parent = match table_at(root, prefix) {
+        Ok(parent) => parent,
+        Err(message) => return Err(error_at(state, start, message)),
+    };
+    match parent.position(last.as_str()) {
+        Some(idx) => match parent.entries[idx].1 {
+            Value::Array(ref mut items) => items.push(Value::Table(Table::new())),
+            _ => return Err(error_at(state, start, format!("key {:?} is not an array of tables", last))),
+        },
+        None => parent.entries.push((last.clone(), Value::Array(vec![Value::Table(Table::new())]))),
+    }
+    Ok(())
+}
+
+fn toml_document() -> Monad<char, (), Table> {
+    let gap = blank("#");
+    let end = line_end("#");
+    let pair = parser(key_value()).bind(bnd!(|_:&mut State<char>, (start, path, value):(usize, Vec<String>, Value)|->Status<Line> {
+        Ok(Line::Pair(start, path, value))
+    }));
+    let line = either(arc!(header()), arc!(pair));
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Table> {
+        let mut root = Table::new();
+        let mut current:Vec<String> = Vec::new();
+        let mut defined = Vec::new();
+        loop {
+            try!(gap.parse(state));
+            if at_eof(state) {
+                return Ok(root);
+            }
+            match try!(line.parse(state)) {
+                Line::Table(start, path) => {
+                    try!(define_table(state, &mut root, &mut defined, start, &path));
+                    current = path;
+                }
+                Line::ArrayTable(start, path) => {
+                    try!(push_table(state, &mut root, start, &path));
+                    current = path;
+                }
+                Line::Pair(start, path, value) => {
+                    let re = match table_at(&mut root, &current[..]) {
+                        Ok(table) => assign(state, table, start, path, value),
+                        Err(message) => Err(error_at(state, start, message)),
+                    };
+                    try!(re);
+                }
+            }
+            try!(end.parse(state));
+        }
+    }))
+}
+
+// Section as [name], the name is trimmed and never dotted.
+fn section() -> Monad<char, char, Line> {
+    let name = chars(arc!(many(arc!(none_of_str("]\r\n")))));
+    let close = expected(arc!(eq(']')), "']'");
+    eq('[').bind(bnd!(move |state:&mut State<char>, _:char|->Status<Line> {
+        let start = state.pos() - 1;
+        let name = try!(name.parse(state));
+        try!(close.parse(state));
+        Ok(Line::Table(start, vec![String::from(name.trim())]))
+    }))
+}
+
+// key = value or key: value, the value is the rest of line trimmed, quotes around it removed.
+fn property() -> Monad<char, (), Line> {
+    let key = expected(arc!(chars(arc!(many1(arc!(none_of_str("=:\r\n")))))), "key");
+    let separator = expected(arc!(try(arc!(one_of_str("=:")))), "'=' or ':'");
+    let rest = chars(arc!(many(arc!(none_of_str("\r\n")))));
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Line> {
+        let start = state.pos();
+        let key = try!(key.parse(state));
+        try!(separator.parse(state));
+        let raw = try!(rest.parse(state));
+        let trimmed = raw.trim();
+        let text = if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+            &trimmed[1..trimmed.len() - 1]
+        } else {
+            trimmed
+        };
+        Ok(Line::Pair(start, vec![String::from(key.trim())], Value::String(String::from(text))))
+    }))
+}
+
+fn ini_document() -> Monad<char, (), Table> {
+    let gap = blank(";#");
+    let end = line_end(";#");
+    let line = either(arc!(section()), arc!(property()));
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Table> {
+        let mut root = Table::new();
+        let mut section:Option<String> = None;
+        loop {
+            try!(gap.parse(state));
+            if at_eof(state) {
+                return Ok(root);
+            }
+            match try!(line.parse(state)) {
+                Line::Table(start, mut path) | Line::ArrayTable(start, mut path) => {
+                    let name = path.pop().unwrap();
+                    if root.position(name.as_str()).is_some() {
+                        return Err(error_at(state, start, format!("section {:?} defined twice", name)));
+                    }
+                    root.entries.push((name.clone(), Value::Table(Table::new())));
+                    section = Some(name);
+                }
+                Line::Pair(start, mut path, value) => {
+                    let target = match section {
+                        Some(ref name) => match table_at(&mut root, &[name.clone()]) {
+                            Ok(table) => table,
+                            Err(message) => return Err(error_at(state, start, message)),
+                        },
+                        None => &mut root,
+                    };
+                    try!(insert(state, target, path.pop().unwrap(), value, start));
+                }
+            }
+            try!(end.parse(state));
+        }
+    }))
+}
+
+// Parse TOML subset: tables, array of tables, dotted keys, strings, integers, floats,
+// booleans, date times, arrays and inline tables.
+pub fn parse_toml(text:&str) -> Status<Table> {
+    let mut state:VecState<char> = text.chars().collect();
+    toml_document().parse(&mut state)
+}
+
+// Parse INI, comment lines start with ; or #, values are the rest of line with spaces trimmed.
+pub fn parse_ini(text:&str) -> Status<Table> {
+    let mut state:VecState<char> = text.chars().collect();
+    ini_document().parse(&mut state)
+}
@@ -0,0 +1,377 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, Monad, M};
+use parsec::atom::{eq, pack, one_of_str, satisfy, peek, expect};
+use parsec::combinator::{either, try, many1, between, sep_by1};
+use parsec::lexeme::Lexeme;
+use parsec::lazy::lazy;
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt;
+use std::char;
+use std::sync::Arc;
+
+pub const DEFAULT_MAX_DEPTH:usize = 128;
+
+// JSON value as RFC 8259. Number keeps the literal text so no precision lost before the user
+// convert it, object keeps members in order of the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn is_null(&self) -> bool {
+        *self == JsonValue::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            JsonValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            JsonValue::String(ref s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            JsonValue::Number(ref n) => n.parse::<f64>().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            JsonValue::Number(ref n) => n.parse::<i64>().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match *self {
+            JsonValue::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    // Member of object by key, the last one wins if the key appear more than once.
+    pub fn get(&self, key:&str) -> Option<&JsonValue> {
+        match *self {
+            JsonValue::Object(ref members) => {
+                members.iter().rev().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn write_string(formatter:&mut Formatter, text:&str) -> Result<(), fmt::Error> {
+    try!(write!(formatter, "\""));
+    for c in text.chars() {
+        match c {
+            '"' => try!(write!(formatter, "\\\"")),
+            '\\' => try!(write!(formatter, "\\\\")),
+            '\n' => try!(write!(formatter, "\\n")),
+            '\r' => try!(write!(formatter, "\\r")),
+            '\t' => try!(write!(formatter, "\\t")),
+            '\u{8}' => try!(write!(formatter, "\\b")),
+            '\u{c}' => try!(write!(formatter, "\\f")),
+            c if (c as u32) < 0x20 => try!(write!(formatter, "\\u{:04x}", c as u32)),
+            c => try!(write!(formatter, "{}", c)),
+        }
+    }
+    write!(formatter, "\"")
+}
+
+// Compact serializer, parse(format!("{}", value)) get the same value.
+impl Display for JsonValue {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        match *self {
+            JsonValue::Null => write!(formatter, "null"),
+            JsonValue::Bool(b) => write!(formatter, "{}", b),
+            JsonValue::Number(ref n) => write!(formatter, "{}", n),
+            JsonValue::String(ref s) => write_string(formatter, s.as_str()),
+            JsonValue::Array(ref items) => {
+                try!(write!(formatter, "["));
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        try!(write!(formatter, ","));
+                    }
+                    try!(write!(formatter, "{}", item));
+                }
+                write!(formatter, "]")
+            }
+            JsonValue::Object(ref members) => {
+                try!(write!(formatter, "{{"));
+                for (idx, &(ref key, ref value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        try!(write!(formatter, ","));
+                    }
+                    try!(write_string(formatter, key.as_str()));
+                    try!(write!(formatter, ":{}", value));
+                }
+                write!(formatter, "}}")
+            }
+        }
+    }
+}
+
+// JSON only allows space, tab, carriage return and newline between tokens.
+fn json_lexer() -> Lexeme {
+    Lexeme::white(arc!(one_of_str(" \t\r\n")))
+}
+
+// DIGIT of RFC 8259 is only 0 to 9, other numeric chars are not digits of a number.
+fn digits() -> Monad<char, Vec<char>, String> {
+    let digit = satisfy(bnd!(|c:&char| *c >= '0' && *c <= '9'), String::from("digit"));
+    many1(arc!(digit)).bind(bnd!(|_:&mut State<char>, x:Vec<char>|->Status<String> {
+        Ok(x.into_iter().collect())
+    }))
+}
+
+// Keeps the literal text, a leading zero must be the whole integer part and fraction or exponent
+// must have digits.
+fn number() -> Monad<char, (), JsonValue> {
+    let sign = either(arc!(eq('-').bind(bnd!(|_:&mut State<char>, c:char|->Status<String> {
+        Ok(c.to_string())
+    }))), arc!(pack(String::new())));
+    let int = either(arc!(eq('0').bind(bnd!(|_:&mut State<char>, c:char|->Status<String> {
+        Ok(c.to_string())
+    }))), arc!(digits()));
+    let frac = either(arc!(eq('.').then(arc!(digits())).bind(bnd!(|_:&mut State<char>, x:String|->Status<String> {
+        Ok(format!(".{}", x))
+    }))), arc!(pack(String::new())));
+    let exp_sign = either(arc!(try(arc!(one_of_str("+-"))).bind(bnd!(|_:&mut State<char>, c:char|->Status<String> {
+        Ok(c.to_string())
+    }))), arc!(pack(String::new())));
+    let exp = either(arc!(try(arc!(one_of_str("eE"))).bind(bnd!(move |state:&mut State<char>, e:char|->Status<String> {
+        let sign = try!(exp_sign.parse(state));
+        let digits = try!(digits().parse(state));
+        Ok(format!("{}{}{}", e, sign, digits))
+    }))), arc!(pack(String::new())));
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<JsonValue> {
+        let mut literal = try!(sign.parse(state));
+        literal.push_str(try!(int.parse(state)).as_str());
+        literal.push_str(try!(frac.parse(state)).as_str());
+        literal.push_str(try!(exp.parse(state)).as_str());
+        Ok(JsonValue::Number(literal))
+    }))
+}
+
+fn hex4(state:&mut State<char>, start:usize) -> Status<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        match state.next().and_then(|c:char| c.to_digit(16)) {
+            Some(d) => code = code * 16 + d,
+            None => {
+                let message = format!("invalid escape \\u at {}, expect four hex digits", start);
+                return Err(SimpleError::new(start, message));
+            }
+        }
+    }
+    Ok(code)
+}
+
+fn escape(state:&mut State<char>, start:usize) -> Status<char> {
+    let x = state.next();
+    match x {
+        Some('"') => Ok('"'),
+        Some('\\') => Ok('\\'),
+        Some('/') => Ok('/'),
+        Some('b') => Ok('\u{8}'),
+        Some('f') => Ok('\u{c}'),
+        Some('n') => Ok('\n'),
+        Some('r') => Ok('\r'),
+        Some('t') => Ok('\t'),
+        Some('u') => {
+            let high = match hex4(state, start) {
+                Ok(code) => code,
+                Err(err) => return Err(err),
+            };
+            let code = if 0xD800 <= high && high < 0xDC00 {
+                let low_start = state.pos();
+                if state.next() != Some('\\') || state.next() != Some('u') {
+                    let message = format!("unpaired surrogate \\u{:04x} at {}", high, start);
+                    return Err(SimpleError::new(start, message));
+                }
+                let low = match hex4(state, low_start) {
+                    Ok(code) => code,
+                    Err(err) => return Err(err),
+                };
+                if low < 0xDC00 || 0xE000 <= low {
+                    let message = format!("invalid low surrogate \\u{:04x} at {}", low, low_start);
+                    return Err(SimpleError::new(low_start, message));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            } else {
+                high
+            };
+            char::from_u32(code).ok_or_else(|| {
+                let message = format!("unpaired surrogate \\u{:04x} at {}", code, start);
+                SimpleError::new(start, message)
+            })
+        }
+        Some(c) => {
+            let message = format!("unknown escape \\{} at {}", c, start);
+            Err(SimpleError::new(start, message))
+        }
+        None => {
+            let message = format!("unterminated escape at {}", start);
+            Err(SimpleError::new(start, message))
+        }
+    }
+}
+
+
+// Quoted string as string_literal, with JSON escapes and no raw control chars.
+fn string_value() -> Monad<char, char, String> {
+    eq('"').bind(bnd!(|state:&mut State<char>, _:char|->Status<String> {
+        let start = state.pos() - 1;
+        let mut re = String::new();
+        loop {
+            let pos = state.pos();
+            match state.next() {
+                Some('"') => return Ok(re),
+                Some('\\') => re.push(try!(escape(state, pos))),
+                Some(c) if (c as u32) < 0x20 => {
+                    let message = format!("control char {:?} in string at {}", c, pos);
+                    return Err(SimpleError::new(pos, message));
+                }
+                Some(c) => re.push(c),
+                None => {
+                    let message = format!("unterminated string start at {}", start);
+                    return Err(SimpleError::new(start, message));
+                }
+            }
+        }
+    }))
+}
+
+// Items between brackets. sep_by would backtrack a broken first item and report the error at
+// the bracket, so the empty case is only taken when no item starts.
+fn items<R:'static+Clone+Debug+Send+Sync>(lexer:&Lexeme, open:&str, item:Arc<Parsec<char, R>>, close:&str)
+        -> Monad<char, Vec<R>, Vec<R>> {
+    let body = either(arc!(sep_by1(arc!(lexer.symbol(",")), item)), arc!(pack(Vec::new())));
+    between(arc!(lexer.symbol(open)), arc!(body), arc!(lexer.symbol(close)))
+}
+
+fn array(lexer:&Lexeme, value:Arc<Parsec<char, JsonValue>>) -> Monad<char, Vec<JsonValue>, JsonValue> {
+    items(lexer, "[", value, "]").bind(bnd!(|_:&mut State<char>, items:Vec<JsonValue>|->Status<JsonValue> {
+        Ok(JsonValue::Array(items))
+    }))
+}
+
+fn object(lexer:&Lexeme, value:Arc<Parsec<char, JsonValue>>) -> Monad<char, Vec<(String, JsonValue)>, JsonValue> {
+    let key = lexer.lexeme(arc!(string_value())).over(arc!(lexer.symbol(":")));
+    let member = key.bind(bnd!(move |state:&mut State<char>, key:String|->Status<(String, JsonValue)> {
+        value.parse(state).map(|value:JsonValue| (key, value))
+    }));
+    items(lexer, "{", arc!(member), "}").bind(bnd!(|_:&mut State<char>, members:Vec<(String, JsonValue)>|->Status<JsonValue> {
+        Ok(JsonValue::Object(members))
+    }))
+}
+
+// An array or object at max depth, it consumes the bracket so the error is not backtracked.
+fn too_deep(max_depth:usize) -> Monad<char, char, JsonValue> {
+    try(arc!(one_of_str("[{"))).bind(bnd!(move |state:&mut State<char>, _:char|->Status<JsonValue> {
+        let pos = state.pos() - 1;
+        let message = format!("json nested deeper than {} at {}", max_depth, pos);
+        Err(SimpleError::new(pos, message))
+    }))
+}
+
+// Value nested in depth arrays or objects. Every level is a lazy parsec built at its first use,
+// so the depth limit is part of the grammar and only levels the input reaches are built.
+fn value_at(depth:usize, max_depth:usize) -> Arc<Parsec<char, JsonValue>> {
+    let lexer = json_lexer();
+    let container:Arc<Parsec<char, JsonValue>> = if depth >= max_depth {
+        arc!(too_deep(max_depth))
+    } else {
+        let inner:Arc<Parsec<char, JsonValue>> = arc!(lazy(move || value_at(depth + 1, max_depth)));
+        arc!(either(arc!(array(&lexer, inner.clone())), arc!(object(&lexer, inner))))
+    };
+    let string = lexer.lexeme(arc!(string_value().bind(bnd!(|_:&mut State<char>, s:String|->Status<JsonValue> {
+        Ok(JsonValue::String(s))
+    }))));
+    let unexpected = pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<JsonValue> {
+        Err(expect(state, "json value"))
+    }));
+    arc!(either(container, arc!(string))
+        .or(arc!(lexer.symbol("true").then(arc!(pack(JsonValue::Bool(true))))))
+        .or(arc!(lexer.symbol("false").then(arc!(pack(JsonValue::Bool(false))))))
+        .or(arc!(lexer.symbol("null").then(arc!(pack(JsonValue::Null)))))
+        .or(arc!(lexer.lexeme(arc!(number()))))
+        .or(arc!(unexpected)))
+}
+
+// JSON value parser, surrounding white spaces are skipped. Arrays and objects deeper than
+// max depth are rejected.
+#[derive(Debug, Clone)]
+pub struct JsonParser {
+    value: Arc<Parsec<char, JsonValue>>,
+}
+
+impl JsonParser {
+    pub fn new() -> JsonParser {
+        JsonParser::new_with(DEFAULT_MAX_DEPTH)
+    }
+
+    fn new_with(max_depth:usize) -> JsonParser {
+        let value = json_lexer().whole(value_at(0, max_depth));
+        JsonParser{value:arc!(value)}
+    }
+
+    pub fn max_depth(self, depth:usize) -> JsonParser {
+        JsonParser::new_with(depth)
+    }
+}
+
+impl Parsec<char, JsonValue> for JsonParser {
+    fn parse(&self, state:&mut State<char>)->Status<JsonValue> {
+        self.value.parse(state)
+    }
+}
+
+impl<'a> FnOnce<(&'a mut State<char>, )> for JsonParser {
+    type Output = Status<JsonValue>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<char>, )) -> Status<JsonValue> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> FnMut<(&'a mut State<char>, )> for JsonParser {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<char>, )) -> Status<JsonValue> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> Fn<(&'a mut State<char>, )> for JsonParser {
+    extern "rust-call" fn call(&self, args: (&'a mut State<char>, )) -> Status<JsonValue> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl M<char, JsonValue> for JsonParser{}
+
+pub fn json_value() -> JsonParser {
+    JsonParser::new()
+}
+
+// Parse a whole JSON text, nothing but white spaces allowed after the value.
+pub fn parse(text:&str) -> Status<JsonValue> {
+    let mut state:VecState<char> = text.chars().collect();
+    let re = json_value().parse(&mut state);
+    if re.is_ok() && peek(&mut state).is_some() {
+        return Err(expect(&mut state, "end of json text"));
+    }
+    re
+}
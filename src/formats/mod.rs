@@ -0,0 +1,7 @@
+pub mod json;
+pub mod csv;
+pub mod ini;
+pub mod sexpr;
+pub mod datetime;
+pub mod uri;
+pub mod http;
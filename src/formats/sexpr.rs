@@ -0,0 +1,288 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, M};
+use parsec::atom::{pack, string, peek, expect};
+use parsec::combinator::{between, many};
+use parsec::lexeme::Lexeme;
+use parsec::text::{string_literal, int64, float64};
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+pub const DEFAULT_MAX_DEPTH:usize = 128;
+
+// S-expression values, a dotted list as (a b . c) keeps the items before the dot and the tail.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexpr {
+    Symbol(String),
+    Integer(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Node>),
+    Dotted(Vec<Node>, Box<Node>),
+    Quote(Box<Node>),
+}
+
+// Value with its source span, end is the position after the last char of the datum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub value: Sexpr,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Node {
+    pub fn new(value:Sexpr, start:usize, end:usize) -> Node {
+        Node{value:value, start:start, end:end}
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self.value {
+            Sexpr::Symbol(ref name) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<Node>> {
+        match self.value {
+            Sexpr::List(ref items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+fn write_items(formatter:&mut Formatter, items:&Vec<Node>) -> Result<(), fmt::Error> {
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            try!(write!(formatter, " "));
+        }
+        try!(write!(formatter, "{}", item));
+    }
+    Ok(())
+}
+
+impl Display for Sexpr {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        match *self {
+            Sexpr::Symbol(ref name) => write!(formatter, "{}", name),
+            Sexpr::Integer(x) => write!(formatter, "{}", x),
+            Sexpr::Float(x) => {
+                if x.is_finite() && x.fract() == 0.0 {
+                    write!(formatter, "{}.0", x)
+                } else {
+                    write!(formatter, "{}", x)
+                }
+            }
+            Sexpr::Str(ref text) => {
+                try!(write!(formatter, "\""));
+                for c in text.chars() {
+                    match c {
+                        '"' => try!(write!(formatter, "\\\"")),
+                        '\\' => try!(write!(formatter, "\\\\")),
+                        '\n' => try!(write!(formatter, "\\n")),
+                        '\t' => try!(write!(formatter, "\\t")),
+                        c => try!(write!(formatter, "{}", c)),
+                    }
+                }
+                write!(formatter, "\"")
+            }
+            Sexpr::List(ref items) => {
+                try!(write!(formatter, "("));
+                try!(write_items(formatter, items));
+                write!(formatter, ")")
+            }
+            Sexpr::Dotted(ref items, ref tail) => {
+                try!(write!(formatter, "("));
+                try!(write_items(formatter, items));
+                write!(formatter, " . {})", tail)
+            }
+            Sexpr::Quote(ref node) => write!(formatter, "'{}", node),
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        self.value.fmt(formatter)
+    }
+}
+
+fn is_atom_char(c:char) -> bool {
+    !c.is_whitespace() && !"()'\";".contains(c)
+}
+
+// Token starts like a number, so "-" is a symbol but "+1" and ".5" not, "1+" is a symbol
+// after the number parsers stop before its end.
+fn is_numeric(token:&str) -> bool {
+    let chars = token.chars().collect::<Vec<char>>();
+    let mut idx = 0;
+    if idx < chars.len() && (chars[idx] == '+' || chars[idx] == '-') {
+        idx += 1;
+    }
+    if idx < chars.len() && chars[idx] == '.' {
+        idx += 1;
+    }
+    idx < chars.len() && chars[idx].is_digit(10)
+}
+
+fn is_integral(token:&str) -> bool {
+    let digits = token.trim_left_matches(|c:char| c == '+' || c == '-');
+    !digits.is_empty() && digits.chars().all(|c:char| c.is_digit(10) || c == '_')
+}
+
+fn atom(state:&mut State<char>) -> Status<Sexpr> {
+    let start = state.pos();
+    let mut token = String::new();
+    while peek(state).map_or(false, is_atom_char) {
+        token.push(state.next().unwrap());
+    }
+    if token.is_empty() {
+        return Err(expect(state, "datum"));
+    }
+    if token == "." {
+        state.seek_to(start);
+        let message = format!("unexpected '.' at {}", start);
+        return Err(SimpleError::new(start, message));
+    }
+    let end = state.pos();
+    if is_numeric(token.as_str()) {
+        state.seek_to(start);
+        let lower = token.to_lowercase();
+        let radix = lower.contains("0x") || lower.contains("0o") || lower.contains("0b");
+        let value = if !radix && (lower.contains('.') || lower.contains('e')) {
+            float64().parse(state).map(|x:f64| Sexpr::Float(x))
+        } else {
+            int64().parse(state).map(|x:i64| Sexpr::Integer(x))
+        };
+        match value {
+            Ok(value) => {
+                if state.pos() == end {
+                    return Ok(value);
+                }
+            }
+            Err(err) => {
+                if is_integral(token.as_str()) {
+                    return Err(err);
+                }
+            }
+        }
+        state.seek_to(end);
+    }
+    Ok(Sexpr::Symbol(token))
+}
+
+// Reader of one datum and the white spaces and comments after it. Comments are `;` to end of
+// line and nested `#| ... |#` blocks. Lists and quotes nested deeper than max depth are rejected.
+#[derive(Debug, Clone)]
+pub struct SexprReader {
+    lexeme: Lexeme,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl SexprReader {
+    pub fn new() -> SexprReader {
+        let lexeme = Lexeme::new().line_comment(";").block_comment("#|", "|#", true);
+        SexprReader{lexeme:lexeme, depth:0, max_depth:DEFAULT_MAX_DEPTH}
+    }
+
+    pub fn max_depth(self, depth:usize) -> SexprReader {
+        SexprReader{max_depth:depth, ..self}
+    }
+
+    // Reader of the data in a list or quote start at pos.
+    fn nested(&self, pos:usize) -> Status<SexprReader> {
+        if self.depth >= self.max_depth {
+            let message = format!("sexpr nested deeper than {} at {}", self.max_depth, pos);
+            return Err(SimpleError::new(pos, message));
+        }
+        Ok(SexprReader{depth:self.depth + 1, ..self.clone()})
+    }
+
+    // Items of list after "(", many stop at ")", "." or the first item failed, parse that item
+    // again to report its own error.
+    fn items(&self, state:&mut State<char>) -> Status<Sexpr> {
+        let items = match many(arc!(self.clone())).parse(state) {
+            Ok(items) => items,
+            Err(err) => return Err(err),
+        };
+        match peek(state) {
+            Some(')') | None => Ok(Sexpr::List(items)),
+            Some('.') => {
+                if items.is_empty() {
+                    return Err(expect(state, "datum before '.'"));
+                }
+                state.next();
+                self.lexeme.white_space().parse(state).ok();
+                self.parse(state).map(|tail:Node| Sexpr::Dotted(items, Box::new(tail)))
+            }
+            Some(_) => match self.parse(state) {
+                Err(err) => Err(err),
+                Ok(_) => Err(expect(state, "')'")),
+            },
+        }
+    }
+
+    fn datum(&self, state:&mut State<char>) -> Status<Node> {
+        let start = state.pos();
+        let value = match peek(state) {
+            Some('(') => {
+                let reader = try!(self.nested(start));
+                let body = pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Sexpr> {
+                    reader.items(state)
+                }));
+                between(arc!(self.lexeme.symbol("(")), arc!(body), arc!(string(")"))).parse(state)
+            }
+            Some('\'') => {
+                let reader = try!(self.nested(start));
+                state.next();
+                reader.datum(state).map(|node:Node| Sexpr::Quote(Box::new(node)))
+            }
+            Some('"') => string_literal('"').parse(state).map(|text:String| Sexpr::Str(text)),
+            Some(')') => {
+                let message = format!("unexpected ')' at {}", start);
+                Err(SimpleError::new(start, message))
+            }
+            _ => atom(state),
+        };
+        value.map(|value:Sexpr| Node::new(value, start, state.pos()))
+    }
+
+    // Every top level datum of text, white spaces and comments before the first are skipped.
+    pub fn read_all(&self, state:&mut State<char>) -> Status<Vec<Node>> {
+        self.lexeme.white_space().parse(state).ok();
+        let mut re = Vec::new();
+        while peek(state).is_some() {
+            match self.parse(state) {
+                Ok(node) => re.push(node),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(re)
+    }
+}
+
+impl Parsec<char, Node> for SexprReader {
+    fn parse(&self, state:&mut State<char>)->Status<Node> {
+        let re = self.datum(state);
+        if re.is_ok() {
+            self.lexeme.white_space().parse(state).ok();
+        }
+        re
+    }
+}
+
+// Read exactly one datum from text.
+pub fn read(text:&str) -> Status<Node> {
+    let mut state:VecState<char> = text.chars().collect();
+    let reader = SexprReader::new();
+    reader.lexeme.white_space().parse(&mut state).ok();
+    let re = reader.parse(&mut state);
+    if re.is_ok() && peek(&mut state).is_some() {
+        return Err(expect(&mut state, "end of input"));
+    }
+    re
+}
+
+pub fn read_all(text:&str) -> Status<Vec<Node>> {
+    let mut state:VecState<char> = text.chars().collect();
+    SexprReader::new().read_all(&mut state)
+}
@@ -0,0 +1,372 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, Monad, M, parser};
+use parsec::atom::{OneOf, Satisfy, eq, pack, string, satisfy, one_of_str, none_of_str, peek, expect};
+use parsec::combinator::{Either, either, try, many};
+use std::ascii::AsciiExt;
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+// Host of authority. Ipv6 keeps the text inside brackets, IpFuture is the "v1.x" form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Host {
+    RegName(String),
+    Ipv4([u8; 4]),
+    Ipv6(String),
+    IpFuture(String),
+}
+
+// URI reference as RFC 3986, components keep their percent-encoded text so formatting give
+// back the input. The authority exists if and only if host is Some, and empty port is None.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    pub scheme: Option<String>,
+    pub userinfo: Option<String>,
+    pub host: Option<Host>,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Uri {
+    pub fn is_relative(&self) -> bool {
+        self.scheme.is_none()
+    }
+
+    pub fn decoded_path(&self) -> String {
+        percent_decode(self.path.as_str())
+    }
+
+    pub fn decoded_query(&self) -> Option<String> {
+        self.query.as_ref().map(|q:&String| percent_decode(q.as_str()))
+    }
+
+    pub fn decoded_fragment(&self) -> Option<String> {
+        self.fragment.as_ref().map(|f:&String| percent_decode(f.as_str()))
+    }
+}
+
+impl Display for Host {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        match *self {
+            Host::RegName(ref name) => write!(formatter, "{}", name),
+            Host::Ipv4(ref octets) => write!(formatter, "{}.{}.{}.{}", octets[0], octets[1], octets[2], octets[3]),
+            Host::Ipv6(ref address) => write!(formatter, "[{}]", address),
+            Host::IpFuture(ref address) => write!(formatter, "[{}]", address),
+        }
+    }
+}
+
+impl Display for Uri {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        if let Some(ref scheme) = self.scheme {
+            try!(write!(formatter, "{}:", scheme));
+        }
+        if let Some(ref host) = self.host {
+            try!(write!(formatter, "//"));
+            if let Some(ref userinfo) = self.userinfo {
+                try!(write!(formatter, "{}@", userinfo));
+            }
+            try!(write!(formatter, "{}", host));
+            if let Some(port) = self.port {
+                try!(write!(formatter, ":{}", port));
+            }
+        }
+        try!(write!(formatter, "{}", self.path));
+        if let Some(ref query) = self.query {
+            try!(write!(formatter, "?{}", query));
+        }
+        if let Some(ref fragment) = self.fragment {
+            try!(write!(formatter, "#{}", fragment));
+        }
+        Ok(())
+    }
+}
+
+// Decode %XX triplets, invalid triplets are kept and bytes not in UTF-8 become U+FFFD.
+pub fn percent_decode(text:&str) -> String {
+    let bytes = text.as_bytes();
+    let mut re = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            let high = (bytes[idx + 1] as char).to_digit(16);
+            let low = (bytes[idx + 2] as char).to_digit(16);
+            if let (Some(high), Some(low)) = (high, low) {
+                re.push((high * 16 + low) as u8);
+                idx += 3;
+                continue;
+            }
+        }
+        re.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&re).into_owned()
+}
+
+fn is_unreserved(c:char) -> bool {
+    (c.is_alphanumeric() && c.is_ascii()) || "-._~".contains(c)
+}
+
+fn is_sub_delim(c:char) -> bool {
+    "!$&'()*+,;=".contains(c)
+}
+
+pub fn unreserved() -> Satisfy<char> {
+    satisfy(bnd!(|c:&char| is_unreserved(*c)), String::from("unreserved char"))
+}
+
+pub fn sub_delims() -> OneOf<char> {
+    one_of_str("!$&'()*+,;=")
+}
+
+fn hex() -> Satisfy<char> {
+    satisfy(bnd!(|c:&char| c.is_digit(16)), String::from("hex digit"))
+}
+
+// "%" and two hex digits, the error is at the "%".
+pub fn pct_encoded() -> Monad<char, char, String> {
+    eq('%').bind(bnd!(|state:&mut State<char>, _:char|->Status<String> {
+        let start = state.pos() - 1;
+        let high = hex().parse(state);
+        let low = hex().parse(state);
+        match (high, low) {
+            (Ok(high), Ok(low)) => Ok(format!("%{}{}", high, low)),
+            _ => {
+                let message = format!("invalid percent encoding at {}, expect two hex digits", start);
+                Err(SimpleError::new(start, message))
+            }
+        }
+    }))
+}
+
+// Raw text of pct-encoded, unreserved, sub-delims and the extra chars.
+pub fn chars_of(extra:&str) -> Monad<char, Vec<String>, String> {
+    let extra = String::from(extra);
+    let label = format!("unreserved, sub-delims or one of {:?}", extra);
+    let single = satisfy(bnd!(move |c:&char| is_unreserved(*c) || is_sub_delim(*c) || extra.contains(*c)), label)
+        .bind(bnd!(|_:&mut State<char>, c:char|->Status<String> { Ok(c.to_string()) }));
+    let piece:Either<char, String> = either(arc!(pct_encoded()), arc!(single));
+    parser(arc!(many(arc!(piece)))).bind(bnd!(|_:&mut State<char>, pieces:Vec<String>|->Status<String> {
+        Ok(pieces.concat())
+    }))
+}
+
+// Many stop before a broken "%", parse it again to report the error.
+fn component(state:&mut State<char>, extra:&str) -> Status<String> {
+    let re = chars_of(extra).parse(state);
+    if re.is_ok() && peek(state) == Some('%') {
+        return pct_encoded().parse(state);
+    }
+    re
+}
+
+fn scheme() -> Monad<char, (), String> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<String> {
+        let first = satisfy(bnd!(|c:&char| c.is_alphabetic() && c.is_ascii()), String::from("scheme"));
+        let rest = satisfy(bnd!(|c:&char| (c.is_alphanumeric() && c.is_ascii()) || "+-.".contains(*c)),
+                           String::from("scheme char"));
+        let head = match first.parse(state) {
+            Ok(c) => c,
+            Err(err) => return Err(err),
+        };
+        let tail = many(arc!(rest)).parse(state).unwrap();
+        eq(':').parse(state).map(|_| {
+            let mut re = head.to_string();
+            re.extend(tail.into_iter());
+            re
+        })
+    }))
+}
+
+fn userinfo() -> Monad<char, String, String> {
+    chars_of(":").over(arc!(eq('@')))
+}
+
+fn dec_octet(text:&str) -> Option<u8> {
+    if text.is_empty() || text.len() > 3 || (text.len() > 1 && text.starts_with("0"))
+        || !text.chars().all(|c:char| c.is_digit(10)) {
+        return None;
+    }
+    text.parse::<u8>().ok()
+}
+
+fn ipv4(text:&str) -> Option<[u8; 4]> {
+    let parts = text.split('.').collect::<Vec<&str>>();
+    if parts.len() != 4 {
+        return None;
+    }
+    let mut re = [0; 4];
+    for (idx, part) in parts.iter().enumerate() {
+        match dec_octet(part) {
+            Some(octet) => re[idx] = octet,
+            None => return None,
+        }
+    }
+    Some(re)
+}
+
+// Count of 16 bits pieces, None if any group invalid. Ipv4 is allowed as the last group.
+fn h16_groups(text:&str, last:bool) -> Option<usize> {
+    if text.is_empty() {
+        return Some(0);
+    }
+    let groups = text.split(':').collect::<Vec<&str>>();
+    let mut count = 0;
+    for (idx, group) in groups.iter().enumerate() {
+        if last && idx == groups.len() - 1 && group.contains('.') {
+            if ipv4(group).is_none() {
+                return None;
+            }
+            count += 2;
+        } else if group.len() >= 1 && group.len() <= 4 && group.chars().all(|c:char| c.is_digit(16)) {
+            count += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(count)
+}
+
+pub fn is_ipv6(text:&str) -> bool {
+    let halves = text.split("::").collect::<Vec<&str>>();
+    match halves.len() {
+        1 => h16_groups(text, true) == Some(8),
+        2 => {
+            let head = h16_groups(halves[0], false);
+            let tail = h16_groups(halves[1], true);
+            match (head, tail) {
+                (Some(head), Some(tail)) => head + tail <= 7,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn is_ip_future(text:&str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some('v') | Some('V') => (),
+        _ => return false,
+    }
+    let rest = chars.as_str();
+    match rest.find('.') {
+        Some(dot) => {
+            let (version, address) = (&rest[..dot], &rest[dot + 1..]);
+            !version.is_empty() && version.chars().all(|c:char| c.is_digit(16)) && !address.is_empty()
+                && address.chars().all(|c:char| is_unreserved(c) || is_sub_delim(c) || c == ':')
+        }
+        None => false,
+    }
+}
+
+fn ip_literal(state:&mut State<char>) -> Status<Host> {
+    let start = state.pos();
+    let body = eq('[').then(arc!(many(arc!(none_of_str("]/?#@"))))).over(arc!(eq(']')));
+    let text = match body.parse(state) {
+        Ok(chars) => chars.into_iter().collect::<String>(),
+        Err(err) => return Err(err),
+    };
+    if is_ip_future(text.as_str()) {
+        Ok(Host::IpFuture(text))
+    } else if is_ipv6(text.as_str()) {
+        Ok(Host::Ipv6(text))
+    } else {
+        let message = format!("invalid ip literal [{}] at {}", text, start + 1);
+        Err(SimpleError::new(start + 1, message))
+    }
+}
+
+fn host(state:&mut State<char>) -> Status<Host> {
+    if peek(state) == Some('[') {
+        return ip_literal(state);
+    }
+    component(state, "").map(|name:String| match ipv4(name.as_str()) {
+        Some(octets) => Host::Ipv4(octets),
+        None => Host::RegName(name),
+    })
+}
+
+fn port(state:&mut State<char>) -> Status<Option<u16>> {
+    let start = state.pos();
+    let digits = many(arc!(satisfy(bnd!(|c:&char| c.is_digit(10)), String::from("digit")))).parse(state).unwrap();
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    let text = digits.into_iter().collect::<String>();
+    match text.parse::<u32>() {
+        Ok(port) if port <= 65535 => Ok(Some(port as u16)),
+        _ => {
+            let message = format!("port {} out of range 0..65535 at {}", text, start);
+            Err(SimpleError::new(start, message))
+        }
+    }
+}
+
+fn reference(state:&mut State<char>) -> Status<Uri> {
+    let scheme = try(arc!(scheme())).parse(state).ok();
+    let mut uri = Uri{scheme:scheme, userinfo:None, host:None, port:None, path:String::new(),
+                      query:None, fragment:None};
+    if try(arc!(string("//"))).parse(state).is_ok() {
+        uri.userinfo = try(arc!(userinfo())).parse(state).ok();
+        uri.host = match host(state) {
+            Ok(host) => Some(host),
+            Err(err) => return Err(err),
+        };
+        if peek(state) == Some(':') {
+            state.next();
+            uri.port = match port(state) {
+                Ok(port) => port,
+                Err(err) => return Err(err),
+            };
+        }
+    }
+    let path_start = state.pos();
+    if uri.host.is_none() || peek(state) == Some('/') {
+        uri.path = match component(state, ":@/") {
+            Ok(path) => path,
+            Err(err) => return Err(err),
+        };
+    }
+    if uri.scheme.is_none() && uri.host.is_none() {
+        let segment = uri.path.split('/').next().unwrap();
+        if let Some(idx) = segment.find(':') {
+            let pos = path_start + segment[..idx].chars().count();
+            let message = format!("colon in first segment of relative path at {}", pos);
+            return Err(SimpleError::new(pos, message));
+        }
+    }
+    if peek(state) == Some('?') {
+        state.next();
+        uri.query = match component(state, ":@/?") {
+            Ok(query) => Some(query),
+            Err(err) => return Err(err),
+        };
+    }
+    if peek(state) == Some('#') {
+        state.next();
+        uri.fragment = match component(state, ":@/?") {
+            Ok(fragment) => Some(fragment),
+            Err(err) => return Err(err),
+        };
+    }
+    Ok(uri)
+}
+
+// URI or relative reference, it stops before the first char can't be a part of it.
+pub fn uri_reference() -> Monad<char, (), Uri> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<Uri> {
+        reference(state)
+    }))
+}
+
+// Parse whole text as a URI reference.
+pub fn parse(text:&str) -> Status<Uri> {
+    let mut state:VecState<char> = text.chars().collect();
+    let re = reference(&mut state);
+    if re.is_ok() && peek(&mut state).is_some() {
+        return Err(expect(&mut state, "end of uri"));
+    }
+    re
+}
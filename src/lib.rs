@@ -2,5 +2,10 @@
 #![feature(convert)]
 #![feature(unboxed_closures)]
 #![feature(vec_push_all)]
+extern crate regex;
+#[cfg(feature = "bigint")]
+extern crate num;
 pub mod functional;
+#[macro_use]
 pub mod parsec;
+pub mod formats;
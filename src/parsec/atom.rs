@@ -1,12 +1,14 @@
-use parsec::{State, SimpleError, Error, Parsec, Status, M};
+use parsec::{State, SimpleError, Error, Parsec, Status, Monad, M};
+use parsec::unicode;
 use std::fmt::{Debug, Display, Formatter};
 use std::fmt;
 use std::sync::Arc;
+use std::char;
 use std::marker::PhantomData;
 
 #[derive(Debug, Clone)]
 pub struct One<T>{
-    input : PhantomData<T>,
+    input : PhantomData<fn()->T>,
 }
 
 impl<T> One <T> where T:Debug+Clone {
@@ -52,13 +54,13 @@ pub struct Equal<T>{
     element : T,
 }
 
-impl<T> Equal<T> where T:Eq+Display+Debug+Clone {
+impl<T> Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     fn new(element:T) -> Equal<T> {
         Equal{element:element}
     }
 }
 
-impl<T> Parsec<T, T> for Equal<T> where T:Eq+Display+Debug+Clone {
+impl<T> Parsec<T, T> for Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     fn parse(&self, state:&mut State<T>)->Status<T>{
         let ref value = self.element;
         let val = state.next_by(&|val:&T|val.eq(value));
@@ -72,29 +74,29 @@ impl<T> Parsec<T, T> for Equal<T> where T:Eq+Display+Debug+Clone {
     }
 }
 
-impl<'a, T> FnOnce<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> FnOnce<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     type Output = Status<T>;
     extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, T> FnMut<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> FnMut<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, T> Fn<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> Fn<(&'a mut State<T>, )> for Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<T> {
         let (state, ) = args;
         self.parse(state)
     }
 }
 
-impl<T:'static+Eq+Display+Debug+Clone> M<T, T> for Equal<T>{}
+impl<T:'static+Eq+Display+Debug+Clone+Send+Sync> M<T, T> for Equal<T>{}
 
-pub fn eq<T>(element:T) -> Equal<T> where T:Eq+Display+Debug+Clone {
+pub fn eq<T>(element:T) -> Equal<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     Equal::new(element)
 }
 
@@ -103,13 +105,13 @@ pub struct NotEqual<T>{
     element : T,
 }
 
-impl<T> NotEqual<T> where T:Eq+Display+Debug+Clone {
+impl<T> NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     fn new(element:T) -> NotEqual<T> {
         NotEqual{element:element}
     }
 }
 
-impl<T> Parsec<T, T> for NotEqual<T> where T:Eq+Display+Debug+Clone {
+impl<T> Parsec<T, T> for NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     fn parse(&self, state:&mut State<T>)->Status<T>{
         let ref value = self.element;
         let val = state.next_by(&|val:&T|val.ne(value));
@@ -123,34 +125,34 @@ impl<T> Parsec<T, T> for NotEqual<T> where T:Eq+Display+Debug+Clone {
     }
 }
 
-impl<'a, T> FnOnce<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> FnOnce<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     type Output = Status<T>;
     extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, T> FnMut<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> FnMut<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, T> Fn<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone {
+impl<'a, T> Fn<(&'a mut State<T>, )> for NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<T> {
         let (state, ) = args;
         self.parse(state)
     }
 }
 
-impl<T:'static+Eq+Display+Debug+Clone> M<T, T> for NotEqual<T>{}
+impl<T:'static+Eq+Display+Debug+Clone+Send+Sync> M<T, T> for NotEqual<T>{}
 
-pub fn ne<T>(element:T) -> NotEqual<T> where T:Eq+Display+Debug+Clone {
+pub fn ne<T>(element:T) -> NotEqual<T> where T:Eq+Display+Debug+Clone+Send+Sync {
     NotEqual::new(element)
 }
 
 pub struct Eof<T>{
-    data: PhantomData<T>,
+    data: PhantomData<fn()->T>,
 }
 
 impl<T> Eof<T>{
@@ -218,7 +220,7 @@ pub struct OneOf<T> {
     elements: Vec<T>,
 }
 
-impl<T> OneOf<T> where T:Eq+Display+Clone+Debug {
+impl<T> OneOf<T> where T:Eq+Display+Clone+Debug+Send+Sync {
     pub fn new(elements:&Vec<T>) -> OneOf<T> {
         let mut es = Vec::new();
         es.push_all(&elements);
@@ -226,7 +228,7 @@ impl<T> OneOf<T> where T:Eq+Display+Clone+Debug {
     }
 }
 
-impl<T> Parsec<T, T> for OneOf<T> where T:Eq+Display+Clone+Debug {
+impl<T> Parsec<T, T> for OneOf<T> where T:Eq+Display+Clone+Debug+Send+Sync {
     fn parse(&self, state:&mut State<T>)->Status<T>{
         let next = state.next();
         if next.is_none() {
@@ -257,17 +259,17 @@ impl<'a, T> FnMut<(&'a mut State<T>, )> for OneOf<T> {
     }
 }
 
-impl<'a, T> Fn<(&'a mut State<T>, )> for OneOf<T> where T:Eq+Clone+Display+Debug {
+impl<'a, T> Fn<(&'a mut State<T>, )> for OneOf<T> where T:Eq+Clone+Display+Debug+Send+Sync {
     extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<T> {
         let (state, ) = args;
         self.parse(state)
     }
 }
 
-impl<T:'static+Eq+Debug+Display+Clone> M<T, T> for OneOf<T>{}
+impl<T:'static+Eq+Debug+Display+Clone+Send+Sync> M<T, T> for OneOf<T>{}
 
-pub fn one_of<T:'static+Eq+Debug+Display>(elements:&Vec<T>)->OneOf<T>
-        where T:Eq+Display+Clone+Debug {
+pub fn one_of<T:'static+Eq+Debug+Display+Send+Sync>(elements:&Vec<T>)->OneOf<T>
+        where T:Eq+Display+Clone+Debug+Send+Sync {
     OneOf::new(&elements)
 }
 
@@ -276,7 +278,7 @@ pub struct NoneOf<T> {
     elements: Vec<T>,
 }
 
-impl<T> NoneOf<T> where T:Eq+Display+Clone+Debug {
+impl<T> NoneOf<T> where T:Eq+Display+Clone+Debug+Send+Sync {
     pub fn new(elements:&Vec<T>) -> NoneOf<T> {
         let mut es = Vec::new();
         es.push_all(&elements);
@@ -284,7 +286,7 @@ impl<T> NoneOf<T> where T:Eq+Display+Clone+Debug {
     }
 }
 
-impl<T> Parsec<T, T> for NoneOf<T> where T:Eq+Display+Clone+Debug {
+impl<T> Parsec<T, T> for NoneOf<T> where T:Eq+Display+Clone+Debug+Send+Sync {
     fn parse(&self, state:&mut State<T>)->Status<T>{
         let next = state.next();
         if next.is_none() {
@@ -315,58 +317,58 @@ impl<'a, T> FnMut<(&'a mut State<T>, )> for NoneOf<T> {
     }
 }
 
-impl<'a, T> Fn<(&'a mut State<T>, )> for NoneOf<T> where T:Eq+Clone+Display+Debug {
+impl<'a, T> Fn<(&'a mut State<T>, )> for NoneOf<T> where T:Eq+Clone+Display+Debug+Send+Sync {
     extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<T> {
         let (state, ) = args;
         self.parse(state)
     }
 }
 
-impl<T:'static+Eq+Debug+Display+Clone> M<T, T> for NoneOf<T>{}
+impl<T:'static+Eq+Debug+Display+Clone+Send+Sync> M<T, T> for NoneOf<T>{}
 
-pub fn none_of<T:'static+Eq+Debug+Display>(elements:&Vec<T>)->NoneOf<T>
-        where T:Eq+Display+Clone+Debug {
+pub fn none_of<T:'static+Eq+Debug+Display+Send+Sync>(elements:&Vec<T>)->NoneOf<T>
+        where T:Eq+Display+Clone+Debug+Send+Sync {
     NoneOf::new(&elements)
 }
 
 pub struct Pack<I, T>{
     element : T,
-    input_type: PhantomData<I>,
+    input_type: PhantomData<fn()->I>,
 }
 
-impl<I, T> Pack<I, T> where T:Clone+Debug {
+impl<I, T> Pack<I, T> where T:Clone+Debug+Send+Sync {
     fn new(element:T) -> Pack<I, T> {
         Pack{element:element, input_type:PhantomData}
     }
 }
 
-impl<I, T> Parsec<I, T> for Pack<I, T> where T:Clone+Debug {
+impl<I, T> Parsec<I, T> for Pack<I, T> where T:Clone+Debug+Send+Sync {
     fn parse(&self, _:&mut State<I>)->Status<T> {
         Ok(self.element.clone())
     }
 }
 
-impl<'a, I, T> FnOnce<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug {
+impl<'a, I, T> FnOnce<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug+Send+Sync {
     type Output = Status<T>;
     extern "rust-call" fn call_once(self, _: (&'a mut State<I>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, I, T> FnMut<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug {
+impl<'a, I, T> FnMut<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug+Send+Sync {
     extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<I>, )) -> Status<T> {
         panic!("Not implement!");
     }
 }
 
-impl<'a, I, T> Fn<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug {
+impl<'a, I, T> Fn<(&'a mut State<I>, )> for Pack<I, T> where T:Clone+Debug+Send+Sync {
     extern "rust-call" fn call(&self, args: (&'a mut State<I>, )) -> Status<T> {
         let (state, ) = args;
         self.parse(state)
     }
 }
 
-impl<I, T> Clone for Pack<I, T> where T:Clone+Debug {
+impl<I, T> Clone for Pack<I, T> where T:Clone+Debug+Send+Sync {
     fn clone(&self)->Self {
         Pack{element:self.element.clone(), input_type:PhantomData}
     }
@@ -376,22 +378,22 @@ impl<I, T> Clone for Pack<I, T> where T:Clone+Debug {
     }
 }
 
-impl<I, T> Debug for Pack<I, T> where T:Clone+Debug {
+impl<I, T> Debug for Pack<I, T> where T:Clone+Debug+Send+Sync {
     fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
         write!(formatter, "<pack parsec({:?})>", self.element)
     }
 }
 
-impl<I:'static+Clone, T:'static+Debug+Clone> M<I, T> for Pack<I, T>{}
+impl<I:'static+Clone, T:'static+Debug+Clone+Send+Sync> M<I, T> for Pack<I, T>{}
 
-pub fn pack<I, T>(element:T) -> Pack<I, T> where T:Clone+Debug {
+pub fn pack<I, T>(element:T) -> Pack<I, T> where T:Clone+Debug+Send+Sync {
     Pack::new(element)
 }
 
 pub struct Fail<T, R>{
     message:Arc<String>,
-    input_type: PhantomData<T>,
-    output_type: PhantomData<R>,
+    input_type: PhantomData<fn()->T>,
+    output_type: PhantomData<fn()->R>,
 }
 
 impl<T, R> Fail<T, R> where T: Clone, R:Clone {
@@ -448,3 +450,244 @@ impl<T:'static, R:'static> M<T, R> for Fail<T, R> where T:Clone, R:Clone{}
 pub fn fail<T, R>(message:String) -> Fail<T, R> where T:Clone, R:Clone {
     Fail::new(message)
 }
+
+// Match a sequence of items, it is atomic: state don't move if any item mismatch.
+#[derive(Debug, Clone)]
+pub struct Tag<T> {
+    elements: Vec<T>,
+}
+
+impl<T> Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    pub fn new(elements:&Vec<T>) -> Tag<T> {
+        let mut es = Vec::new();
+        es.push_all(&elements);
+        Tag{elements:es}
+    }
+}
+
+impl<T> Parsec<T, Vec<T>> for Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    fn parse(&self, state:&mut State<T>)->Status<Vec<T>>{
+        let start = state.pos();
+        for element in self.elements.iter() {
+            let val = state.next_by(&|val:&T|val.eq(element));
+            if val.is_err() {
+                state.seek_to(start);
+                let message = format!("expect {:?} at {} but mismatch", self.elements, start);
+                return Err(SimpleError::new(start, message));
+            }
+        }
+        Ok(self.elements.clone())
+    }
+}
+
+impl<'a, T> FnOnce<(&'a mut State<T>, )> for Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    type Output = Status<Vec<T>>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<Vec<T>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T> FnMut<(&'a mut State<T>, )> for Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<Vec<T>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T> Fn<(&'a mut State<T>, )> for Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<Vec<T>> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<T:'static+Eq+Clone+Debug+Send+Sync> M<T, Vec<T>> for Tag<T>{}
+
+pub fn tag<T>(elements:&Vec<T>) -> Tag<T> where T:Eq+Clone+Debug+Send+Sync {
+    Tag::new(elements)
+}
+
+// Match a string. The case insensitive match compares chars by simple unicode case folding, so
+// 'ẞ' matches 'ß', 'ς' matches 'Σ' and the Kelvin sign matches 'k'. It maps one char to one
+// char: "straße" don't match "STRASSE", and 'İ' only matches itself. It returns the text from
+// state, so istring("select") may got "SeLeCt".
+#[derive(Debug, Clone)]
+pub struct Text {
+    text: String,
+    ignore_case: bool,
+}
+
+impl Text {
+    pub fn new(text:&str, ignore_case:bool) -> Text {
+        Text{text:String::from(text), ignore_case:ignore_case}
+    }
+}
+
+// Simple case folding of c, or c itself if it has no folding.
+fn fold(c:char) -> char {
+    let code = c as u32;
+    match unicode::CASE_FOLDING.binary_search_by(|&(from, _)| from.cmp(&code)) {
+        Ok(idx) => char::from_u32(unicode::CASE_FOLDING[idx].1).unwrap_or(c),
+        Err(_) => c,
+    }
+}
+
+impl Parsec<char, String> for Text {
+    fn parse(&self, state:&mut State<char>)->Status<String>{
+        let start = state.pos();
+        let mut re = String::new();
+        for c in self.text.chars() {
+            let ignore_case = self.ignore_case;
+            let val = state.next_by(&|x:&char| if ignore_case { fold(*x) == fold(c) } else { *x == c });
+            match val {
+                Ok(x) => re.push(x),
+                Err(_) => {
+                    state.seek_to(start);
+                    let message = if self.ignore_case {
+                        format!("expect {:?} (ignore case) at {} but mismatch", self.text, start)
+                    } else {
+                        format!("expect {:?} at {} but mismatch", self.text, start)
+                    };
+                    return Err(SimpleError::new(start, message));
+                }
+            }
+        }
+        Ok(re)
+    }
+}
+
+impl<'a> FnOnce<(&'a mut State<char>, )> for Text {
+    type Output = Status<String>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<char>, )) -> Status<String> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> FnMut<(&'a mut State<char>, )> for Text {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<char>, )) -> Status<String> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> Fn<(&'a mut State<char>, )> for Text {
+    extern "rust-call" fn call(&self, args: (&'a mut State<char>, )) -> Status<String> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl M<char, String> for Text{}
+
+pub fn string(text:&str) -> Text {
+    Text::new(text, false)
+}
+
+pub fn istring(text:&str) -> Text {
+    Text::new(text, true)
+}
+
+pub fn one_of_str(elements:&str) -> OneOf<char> {
+    one_of(&elements.chars().collect::<Vec<char>>())
+}
+
+pub fn none_of_str(elements:&str) -> NoneOf<char> {
+    none_of(&elements.chars().collect::<Vec<char>>())
+}
+
+// Match one item by predicate, label is what we expect and used in error message.
+pub struct Satisfy<T> {
+    pred: Arc<Box<Fn(&T)->bool+Send+Sync>>,
+    label: Arc<String>,
+}
+
+impl<T> Satisfy<T> where T:Clone+Debug {
+    pub fn new(pred:Arc<Box<Fn(&T)->bool+Send+Sync>>, label:String) -> Satisfy<T> {
+        Satisfy{pred:pred.clone(), label:Arc::new(label)}
+    }
+}
+
+impl<T> Parsec<T, T> for Satisfy<T> where T:Clone+Debug {
+    fn parse(&self, state:&mut State<T>)->Status<T>{
+        let ref pred = self.pred;
+        let val = state.next_by(&|x:&T| (pred)(x));
+        val.map_err(
+                |_:SimpleError|{
+                    let pos = state.pos();
+                    let message = match state.next() {
+                        Some(x) => {
+                            state.seek_to(pos);
+                            format!("expect {} at {} but got {:?}", self.label, pos, x)
+                        }
+                        None => format!("expect {} at {} but got eof", self.label, pos),
+                    };
+                    SimpleError::new(pos, message)
+                })
+    }
+}
+
+impl<'a, T> FnOnce<(&'a mut State<T>, )> for Satisfy<T> where T:Clone+Debug {
+    type Output = Status<T>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<T> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T> FnMut<(&'a mut State<T>, )> for Satisfy<T> where T:Clone+Debug {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<T> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T> Fn<(&'a mut State<T>, )> for Satisfy<T> where T:Clone+Debug {
+    extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<T> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<T> Clone for Satisfy<T> where T:Clone+Debug {
+    fn clone(&self)->Self {
+        Satisfy{pred:self.pred.clone(), label:self.label.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.pred = source.pred.clone();
+        self.label = source.label.clone();
+    }
+}
+
+impl<T> Debug for Satisfy<T> where T:Clone+Debug {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "<satisfy parsec: {}>", self.label)
+    }
+}
+
+impl<T:'static+Clone+Debug> M<T, T> for Satisfy<T>{}
+
+pub fn satisfy<T>(pred:Arc<Box<Fn(&T)->bool+Send+Sync>>, label:String) -> Satisfy<T> where T:Clone+Debug {
+    Satisfy::new(pred, label)
+}
+
+// Next item of state without consuming it.
+pub fn peek<T>(state:&mut State<T>) -> Option<T> {
+    let pos = state.pos();
+    let re = state.next();
+    state.seek_to(pos);
+    re
+}
+
+// Error at current position for what a parser expects, with the item got there.
+pub fn expect<T:Debug>(state:&mut State<T>, what:&str) -> SimpleError {
+    let pos = state.pos();
+    let message = match peek(state) {
+        Some(x) => format!("expect {} at {} but got {:?}", what, pos, x),
+        None => format!("expect {} at {} but got eof", what, pos),
+    };
+    SimpleError::new(pos, message)
+}
+
+// Build a parsec from a function which scans state directly.
+pub fn scan<T:'static+Clone, R:'static+Clone>(f:Arc<Box<Fn(&mut State<T>)->Status<R>+Send+Sync>>) -> Monad<T, (), R> {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<R> {
+        (f)(state)
+    }))
+}
@@ -0,0 +1,194 @@
+use parsec::atom::{Satisfy, satisfy};
+use parsec::unicode;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+// Unicode general categories, L is Lu, Ll, Lt, Lm and Lo, N is Nd, Nl and No, and P is every
+// punctuation category. Matched by the generated tables of the unicode module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Lu,
+    Ll,
+    L,
+    Nd,
+    N,
+    P,
+    Zs,
+    Cc,
+}
+
+// Scripts approximated by their main blocks, it is not the unicode Script property: the
+// blocks include unassigned and shared chars, and chars of a script out of these blocks are
+// missed. Good for telling scripts apart, not for validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptBlocks {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+}
+
+static LATIN: &'static [(u32, u32)] = &[
+    (0x41, 0x5A), (0x61, 0x7A), (0xAA, 0xAA), (0xBA, 0xBA), (0xC0, 0xD6), (0xD8, 0xF6),
+    (0xF8, 0x24F), (0x1D00, 0x1D25), (0x1E00, 0x1EFF), (0x2C60, 0x2C7F), (0xA722, 0xA7FF),
+    (0xFB00, 0xFB06), (0xFF21, 0xFF3A), (0xFF41, 0xFF5A),
+];
+
+static GREEK: &'static [(u32, u32)] = &[
+    (0x370, 0x373), (0x375, 0x377), (0x37A, 0x37D), (0x37F, 0x37F), (0x384, 0x384),
+    (0x386, 0x386), (0x388, 0x3E1), (0x3F0, 0x3FF), (0x1F00, 0x1FFE),
+];
+
+static CYRILLIC: &'static [(u32, u32)] = &[(0x400, 0x52F), (0x1C80, 0x1C88), (0x2DE0, 0x2DFF), (0xA640, 0xA69F)];
+static ARMENIAN: &'static [(u32, u32)] = &[(0x531, 0x556), (0x559, 0x58A), (0x58D, 0x58F), (0xFB13, 0xFB17)];
+static HEBREW: &'static [(u32, u32)] = &[(0x591, 0x5F4), (0xFB1D, 0xFB4F)];
+static ARABIC: &'static [(u32, u32)] = &[(0x600, 0x6FF), (0x750, 0x77F), (0x8A0, 0x8FF), (0xFB50, 0xFDFF), (0xFE70, 0xFEFC)];
+static DEVANAGARI: &'static [(u32, u32)] = &[(0x900, 0x97F), (0xA8E0, 0xA8FF)];
+static THAI: &'static [(u32, u32)] = &[(0xE01, 0xE3A), (0xE40, 0xE5B)];
+static HANGUL: &'static [(u32, u32)] = &[(0x1100, 0x11FF), (0x3131, 0x318E), (0xA960, 0xA97C), (0xAC00, 0xD7A3), (0xD7B0, 0xD7FB)];
+static HIRAGANA: &'static [(u32, u32)] = &[(0x3041, 0x3096), (0x309D, 0x309F)];
+static KATAKANA: &'static [(u32, u32)] = &[(0x30A1, 0x30FA), (0x30FD, 0x30FF), (0x31F0, 0x31FF), (0xFF66, 0xFF6F), (0xFF71, 0xFF9D)];
+static HAN: &'static [(u32, u32)] = &[
+    (0x2E80, 0x2E99), (0x2E9B, 0x2EF3), (0x2F00, 0x2FD5), (0x3005, 0x3005), (0x3007, 0x3007),
+    (0x3021, 0x3029), (0x3038, 0x303B), (0x3400, 0x4DBF), (0x4E00, 0x9FFF), (0xF900, 0xFAFF),
+    (0x20000, 0x2FA1F),
+];
+
+// Ranges are sorted and don't overlap.
+fn in_ranges(c:char, ranges:&[(u32, u32)]) -> bool {
+    let code = c as u32;
+    ranges.binary_search_by(|&(from, to)| {
+        if to < code {
+            Ordering::Less
+        } else if from > code {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }).is_ok()
+}
+
+impl Category {
+    pub fn contains(&self, c:char) -> bool {
+        let ranges = match *self {
+            Category::Lu => unicode::UPPERCASE_LETTERS,
+            Category::Ll => unicode::LOWERCASE_LETTERS,
+            Category::L => unicode::LETTERS,
+            Category::Nd => unicode::DECIMAL_NUMBERS,
+            Category::N => unicode::NUMBERS,
+            Category::P => unicode::PUNCTUATIONS,
+            Category::Zs => unicode::SPACE_SEPARATORS,
+            Category::Cc => unicode::CONTROLS,
+        };
+        in_ranges(c, ranges)
+    }
+}
+
+impl ScriptBlocks {
+    pub fn contains(&self, c:char) -> bool {
+        let ranges = match *self {
+            ScriptBlocks::Latin => LATIN,
+            ScriptBlocks::Greek => GREEK,
+            ScriptBlocks::Cyrillic => CYRILLIC,
+            ScriptBlocks::Armenian => ARMENIAN,
+            ScriptBlocks::Hebrew => HEBREW,
+            ScriptBlocks::Arabic => ARABIC,
+            ScriptBlocks::Devanagari => DEVANAGARI,
+            ScriptBlocks::Thai => THAI,
+            ScriptBlocks::Hangul => HANGUL,
+            ScriptBlocks::Hiragana => HIRAGANA,
+            ScriptBlocks::Katakana => KATAKANA,
+            ScriptBlocks::Han => HAN,
+        };
+        in_ranges(c, ranges)
+    }
+}
+
+// A set of chars, build it by range, chars, category and script blocks then union or negate them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharClass {
+    Chars(Vec<char>),
+    Range(char, char),
+    Category(Category),
+    ScriptBlocks(ScriptBlocks),
+    Union(Vec<CharClass>),
+    Negate(Box<CharClass>),
+}
+
+impl CharClass {
+    pub fn contains(&self, c:char) -> bool {
+        match *self {
+            CharClass::Chars(ref chars) => chars.contains(&c),
+            CharClass::Range(from, to) => from <= c && c <= to,
+            CharClass::Category(ref category) => category.contains(c),
+            CharClass::ScriptBlocks(ref script) => script.contains(c),
+            CharClass::Union(ref classes) => classes.iter().any(|class:&CharClass| class.contains(c)),
+            CharClass::Negate(ref class) => !class.contains(c),
+        }
+    }
+
+    pub fn union(self, other:CharClass) -> CharClass {
+        match self {
+            CharClass::Union(mut classes) => {
+                classes.push(other);
+                CharClass::Union(classes)
+            }
+            class => CharClass::Union(vec![class, other]),
+        }
+    }
+
+    pub fn negate(self) -> CharClass {
+        match self {
+            CharClass::Negate(class) => *class,
+            class => CharClass::Negate(Box::new(class)),
+        }
+    }
+
+    pub fn parser(&self) -> Satisfy<char> {
+        let class = self.clone();
+        let label = format!("{}", self);
+        satisfy(bnd!(move |c:&char| class.contains(*c)), label)
+    }
+}
+
+impl Display for CharClass {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        match *self {
+            CharClass::Chars(ref chars) => write!(formatter, "{:?}", chars.iter().cloned().collect::<String>()),
+            CharClass::Range(from, to) => write!(formatter, "{:?}..{:?}", from, to),
+            CharClass::Category(ref category) => write!(formatter, "\\p{{{:?}}}", category),
+            CharClass::ScriptBlocks(ref script) => write!(formatter, "\\p{{{:?} blocks}}", script),
+            CharClass::Union(ref classes) => {
+                let items = classes.iter().map(|class:&CharClass| format!("{}", class)).collect::<Vec<String>>();
+                write!(formatter, "[{}]", items.join(" | "))
+            }
+            CharClass::Negate(ref class) => write!(formatter, "not {}", class),
+        }
+    }
+}
+
+pub fn range(from:char, to:char) -> CharClass {
+    CharClass::Range(from, to)
+}
+
+pub fn chars(elements:&str) -> CharClass {
+    CharClass::Chars(elements.chars().collect())
+}
+
+pub fn category(category:Category) -> CharClass {
+    CharClass::Category(category)
+}
+
+pub fn script_blocks(script:ScriptBlocks) -> CharClass {
+    CharClass::ScriptBlocks(script)
+}
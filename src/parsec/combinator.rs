@@ -1,8 +1,9 @@
-use parsec::{State, Parsec, Status, Monad, monad, M, parser};
+use parsec::{State, SimpleError, Error, Parsec, Status, Monad, monad, M, parser};
 use parsec::atom::{pack, fail};
 use std::sync::Arc;
 use std::fmt::{Debug, Formatter};
 use std::fmt;
+use std::usize;
 
 pub struct Try<T, R>{
     parsec : Arc<Parsec<T, R>>,
@@ -144,11 +145,11 @@ pub fn either<T:'static, R:'static>(x: Arc<Parsec<T, R>>, y:Arc<Parsec<T, R>>)->
 }
 
 pub fn many<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Either<T, Vec<R>>
-where T:Clone, R:Clone+Debug {
+where T:Clone, R:Clone+Debug+Send+Sync {
     either(Arc::new(many1(Arc::new(try(p)))), Arc::new(pack(Vec::new())))
 }
 
-pub fn many1<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Monad<T, R, Vec<R>> where T:Clone, R:Clone+Debug {
+pub fn many1<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Monad<T, R, Vec<R>> where T:Clone, R:Clone+Debug+Send+Sync {
     parser(p.clone()).bind(Arc::new(Box::new(move |state: &mut State<T>, x: R| -> Status<Vec<R>> {
         let mut rev = Vec::new();
         let tail = many(p.clone()).parse(state);
@@ -173,14 +174,14 @@ where T:Clone, R:Clone {
 
 pub fn many_tail<T:'static, R:'static, Tail:'static>(p:Arc<Parsec<T, R>>, tail:Arc<Parsec<T, Tail>>)
     ->Monad<T, Vec<R>, Vec<R>>
-where T:Clone, R:Clone+Debug, Tail:Clone{
+where T:Clone, R:Clone+Debug+Send+Sync, Tail:Clone{
     // TODO: A fake binder between p and tail, someone manybe remove it.
     parser(Arc::new(many(p))).over(tail)
 }
 
 pub fn many1_tail<T:'static, R:'static, Tail:'static>(p:Arc<Parsec<T, R>>, tail:Arc<Parsec<T, Tail>>)
     ->Monad<T, Vec<R>, Vec<R>>
-where T:Clone, R:Clone+Debug, Tail:Clone{
+where T:Clone, R:Clone+Debug+Send+Sync, Tail:Clone{
     // TODO: A fake binder between p and tail, someone manybe remove it.
     parser(Arc::new(many1(p))).over(tail)
 }
@@ -315,21 +316,132 @@ pub fn skip_many1<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Skip1<T, R> where
 }
 
 pub fn sep_by<T:'static, Sep:'static, R:'static>(sep:Arc<Parsec<T, Sep>>, parsec:Arc<Parsec<T, R>>)->Either<T, Vec<R>>
-where T:Clone, R:Clone+Debug, Sep:Clone{
+where T:Clone, R:Clone+Debug+Send+Sync, Sep:Clone{
     let s = Arc::new(try(sep));
     let p = Arc::new(try(parsec));
     either(Arc::new(sep_by1(s, p)), Arc::new(pack(Vec::new())))
 }
 
+// Items separated by sep, every item after the first must follow a separator, and a trailing
+// separator without an item is left in the state.
 pub fn sep_by1<T:'static, Sep:'static, R:'static>(sep:Arc<Parsec<T, Sep>>, parsec:Arc<Parsec<T, R>>)
     ->Monad<T, R, Vec<R>>
-where T:Clone, R:Clone+Debug, Sep:Clone{
+where T:Clone, R:Clone+Debug+Send+Sync, Sep:Clone{
     monad(parsec.clone()).bind(Arc::new(Box::new(move |state:&mut State<T>, x:R|->Status<Vec<R>>{
         let mut rev = Vec::new();
-        let tail = sep_by(sep.clone(), parsec.clone()).parse(state);
+        let tail = many(Arc::new(parser(sep.clone()).then(parsec.clone()))).parse(state);
         let data = tail.unwrap();
         rev.push(x);
         rev.push_all(&data);
         Ok(rev)
     })))
 }
+
+// Some(x) if p success, or None without consume anything.
+pub fn optional<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Either<T, Option<R>>
+where T:Clone, R:Clone+Debug+Send+Sync {
+    let some = parser(Arc::new(try(p))).bind(Arc::new(Box::new(|_:&mut State<T>, x:R|->Status<Option<R>> {
+        Ok(Some(x))
+    })));
+    either(Arc::new(some), Arc::new(pack(None)))
+}
+
+// Run p and rewind, so the result of p is got without consume anything.
+pub fn look_ahead<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Monad<T, (), R> where T:Clone, R:Clone+Debug {
+    pack(()).bind(Arc::new(Box::new(move |state:&mut State<T>, _:()|->Status<R> {
+        let pos = state.pos();
+        let re = p.parse(state);
+        state.seek_to(pos);
+        re
+    })))
+}
+
+// Success without consume anything if p failed, or failed at current position if p success.
+pub fn not_followed_by<T:'static, R:'static>(p:Arc<Parsec<T, R>>)->Monad<T, (), ()> where T:Clone, R:Clone {
+    pack(()).bind(Arc::new(Box::new(move |state:&mut State<T>, _:()|->Status<()> {
+        let pos = state.pos();
+        let re = p.parse(state);
+        state.seek_to(pos);
+        match re {
+            Ok(_) => Err(SimpleError::new(pos, format!("unexpected input at {}", pos))),
+            Err(_) => Ok(()),
+        }
+    })))
+}
+
+fn bounds(min:usize, max:usize) -> String {
+    if max == usize::MAX {
+        format!("at least {}", min)
+    } else if min == max {
+        format!("{}", min)
+    } else {
+        format!("{} to {}", min, max)
+    }
+}
+
+// Run p greedily up to max times and give every result to keep. It stops at an item consume
+// nothing, else it would repeat forever. The failed item is rewound, and if less than min items
+// matched, the error names how many are expected and found.
+fn repeat<T, R>(p:&Parsec<T, R>, min:usize, max:usize, state:&mut State<T>, keep:&mut FnMut(R)) -> Status<()> {
+    let mut count = 0;
+    let mut failed = None;
+    while count < max {
+        let pos = state.pos();
+        match p.parse(state) {
+            Ok(x) => {
+                keep(x);
+                count += 1;
+                if state.pos() == pos {
+                    break;
+                }
+            }
+            Err(err) => {
+                state.seek_to(pos);
+                failed = Some(err);
+                break;
+            }
+        }
+    }
+    if count >= min {
+        return Ok(());
+    }
+    let pos = state.pos();
+    let reason = failed.map_or(String::from("it consume nothing"), |err:SimpleError| String::from(err.message()));
+    let message = format!("expect {} items but got {} at {}, {}", bounds(min, max), count, pos, reason);
+    Err(SimpleError::new(pos, message))
+}
+
+// Between min and max results of p, it stops at max even if p could match more.
+pub fn many_m_n<T:'static, R:'static>(min:usize, max:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), Vec<R>>
+where T:Clone, R:Clone+Debug+Send+Sync {
+    pack(()).bind(Arc::new(Box::new(move |state:&mut State<T>, _:()|->Status<Vec<R>> {
+        let mut re = Vec::new();
+        try!(repeat(&*p, min, max, state, &mut |x:R| re.push(x)));
+        Ok(re)
+    })))
+}
+
+pub fn at_most<T:'static, R:'static>(n:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), Vec<R>>
+where T:Clone, R:Clone+Debug+Send+Sync {
+    many_m_n(0, n, p)
+}
+
+pub fn at_least<T:'static, R:'static>(n:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), Vec<R>>
+where T:Clone, R:Clone+Debug+Send+Sync {
+    many_m_n(n, usize::MAX, p)
+}
+
+pub fn skip_many_m_n<T:'static, R:'static>(min:usize, max:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), ()>
+where T:Clone, R:Clone {
+    pack(()).bind(Arc::new(Box::new(move |state:&mut State<T>, _:()|->Status<()> {
+        repeat(&*p, min, max, state, &mut |_:R| ())
+    })))
+}
+
+pub fn skip_at_most<T:'static, R:'static>(n:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), ()> where T:Clone, R:Clone {
+    skip_many_m_n(0, n, p)
+}
+
+pub fn skip_at_least<T:'static, R:'static>(n:usize, p:Arc<Parsec<T, R>>)->Monad<T, (), ()> where T:Clone, R:Clone {
+    skip_many_m_n(n, usize::MAX, p)
+}
@@ -0,0 +1,329 @@
+use parsec::{State, Parsec, Status, Monad, M};
+use parsec::atom::pack;
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+// Concrete syntax tree. node(name, p) wraps the text p matched into a SyntaxNode, the nodes and
+// tokens in the result of p become its children, and the text between them is filled as tokens,
+// white space as trivia and others as "text" tokens. So the tree keeps every char of the input
+// and text() gives it back. Positions are char offsets of the state.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: String,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+    Trivia(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<SyntaxElement>,
+}
+
+// Result of p with the positions before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<R> {
+    pub value: R,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SyntaxElement {
+    pub fn start(&self) -> usize {
+        match *self {
+            SyntaxElement::Node(ref node) => node.start,
+            SyntaxElement::Token(ref token) | SyntaxElement::Trivia(ref token) => token.start,
+        }
+    }
+
+    pub fn end(&self) -> usize {
+        match *self {
+            SyntaxElement::Node(ref node) => node.end,
+            SyntaxElement::Token(ref token) | SyntaxElement::Trivia(ref token) => token.end,
+        }
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        match *self {
+            SyntaxElement::Trivia(_) => true,
+            _ => false,
+        }
+    }
+
+    fn write_text(&self, re:&mut String) {
+        match *self {
+            SyntaxElement::Node(ref node) => node.write_text(re),
+            SyntaxElement::Token(ref token) | SyntaxElement::Trivia(ref token) => re.push_str(token.text.as_str()),
+        }
+    }
+}
+
+impl SyntaxNode {
+    // Source text under the node, trivia included.
+    pub fn text(&self) -> String {
+        let mut re = String::new();
+        self.write_text(&mut re);
+        re
+    }
+
+    fn write_text(&self, re:&mut String) {
+        for child in self.children.iter() {
+            child.write_text(re);
+        }
+    }
+
+    pub fn nodes(&self) -> Vec<&SyntaxNode> {
+        self.children.iter().filter_map(|child:&SyntaxElement| match *child {
+            SyntaxElement::Node(ref node) => Some(node),
+            _ => None,
+        }).collect()
+    }
+
+    // Tokens and trivia of the whole subtree in source order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut re = Vec::new();
+        for child in self.children.iter() {
+            match *child {
+                SyntaxElement::Node(ref node) => re.extend(node.tokens().into_iter()),
+                SyntaxElement::Token(ref token) | SyntaxElement::Trivia(ref token) => re.push(token),
+            }
+        }
+        re
+    }
+
+    // The node and all nodes under it in preorder.
+    pub fn descendants(&self) -> Vec<&SyntaxNode> {
+        let mut re = vec![self];
+        for node in self.nodes() {
+            re.extend(node.descendants().into_iter());
+        }
+        re
+    }
+
+    pub fn find(&self, name:&str) -> Option<&SyntaxNode> {
+        self.descendants().into_iter().find(|node:&&SyntaxNode| node.name == name)
+    }
+
+    pub fn find_all(&self, name:&str) -> Vec<&SyntaxNode> {
+        self.descendants().into_iter().filter(|node:&&SyntaxNode| node.name == name).collect()
+    }
+
+    // Innermost node covers the position.
+    pub fn node_at(&self, pos:usize) -> Option<&SyntaxNode> {
+        if pos < self.start || pos >= self.end {
+            return None;
+        }
+        for node in self.nodes() {
+            if let Some(inner) = node.node_at(pos) {
+                return Some(inner);
+            }
+        }
+        Some(self)
+    }
+
+    // A line for every node and token, children indented by two spaces.
+    pub fn pretty(&self) -> String {
+        let mut re = String::new();
+        self.write_pretty(0, &mut re);
+        re
+    }
+
+    fn write_pretty(&self, depth:usize, re:&mut String) {
+        let indent = (0..depth).map(|_| "  ").collect::<String>();
+        re.push_str(format!("{}{}@{}..{}\n", indent, self.name, self.start, self.end).as_str());
+        for child in self.children.iter() {
+            match *child {
+                SyntaxElement::Node(ref node) => node.write_pretty(depth + 1, re),
+                SyntaxElement::Token(ref token) | SyntaxElement::Trivia(ref token) => {
+                    re.push_str(format!("{}  {}@{}..{} {:?}\n", indent, token.kind, token.start, token.end, token.text).as_str());
+                }
+            }
+        }
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "{}", self.text())
+    }
+}
+
+// Values a node takes its children from.
+pub trait IntoElements {
+    fn elements(self) -> Vec<SyntaxElement>;
+}
+
+impl IntoElements for () {
+    fn elements(self) -> Vec<SyntaxElement> { Vec::new() }
+}
+
+impl IntoElements for char {
+    fn elements(self) -> Vec<SyntaxElement> { Vec::new() }
+}
+
+impl IntoElements for String {
+    fn elements(self) -> Vec<SyntaxElement> { Vec::new() }
+}
+
+impl IntoElements for SyntaxNode {
+    fn elements(self) -> Vec<SyntaxElement> { vec![SyntaxElement::Node(self)] }
+}
+
+impl IntoElements for SyntaxElement {
+    fn elements(self) -> Vec<SyntaxElement> { vec![self] }
+}
+
+impl<R:IntoElements> IntoElements for Spanned<R> {
+    fn elements(self) -> Vec<SyntaxElement> { self.value.elements() }
+}
+
+impl<R:IntoElements> IntoElements for Option<R> {
+    fn elements(self) -> Vec<SyntaxElement> {
+        self.map_or(Vec::new(), |x:R| x.elements())
+    }
+}
+
+impl<R:IntoElements> IntoElements for Vec<R> {
+    fn elements(self) -> Vec<SyntaxElement> {
+        self.into_iter().flat_map(|x:R| x.elements().into_iter()).collect()
+    }
+}
+
+impl<A:IntoElements, B:IntoElements> IntoElements for (A, B) {
+    fn elements(self) -> Vec<SyntaxElement> {
+        let (a, b) = self;
+        let mut re = a.elements();
+        re.extend(b.elements().into_iter());
+        re
+    }
+}
+
+impl<A:IntoElements, B:IntoElements, C:IntoElements> IntoElements for (A, B, C) {
+    fn elements(self) -> Vec<SyntaxElement> {
+        let (a, b, c) = self;
+        ((a, b), c).elements()
+    }
+}
+
+impl<A:IntoElements, B:IntoElements, C:IntoElements, D:IntoElements> IntoElements for (A, B, C, D) {
+    fn elements(self) -> Vec<SyntaxElement> {
+        let (a, b, c, d) = self;
+        ((a, b), (c, d)).elements()
+    }
+}
+
+// Read back the chars from start to current position.
+fn source(state:&mut State<char>, start:usize) -> Vec<char> {
+    let end = state.pos();
+    state.seek_to(start);
+    let mut re = Vec::new();
+    while state.pos() < end {
+        match state.next() {
+            Some(c) => re.push(c),
+            None => break,
+        }
+    }
+    re
+}
+
+fn token_of(kind:&str, start:usize, text:&[char]) -> SyntaxToken {
+    SyntaxToken{kind:String::from(kind), start:start, end:start + text.len(), text:text.iter().cloned().collect()}
+}
+
+// Split text not covered by children into runs of white space and others.
+fn fill(text:&[char], offset:usize, from:usize, to:usize, re:&mut Vec<SyntaxElement>) {
+    let mut pos = from;
+    while pos < to {
+        let space = text[pos - offset].is_whitespace();
+        let mut end = pos;
+        while end < to && text[end - offset].is_whitespace() == space {
+            end += 1;
+        }
+        let run = &text[pos - offset..end - offset];
+        re.push(if space {
+            SyntaxElement::Trivia(token_of("whitespace", pos, run))
+        } else {
+            SyntaxElement::Token(token_of("text", pos, run))
+        });
+        pos = end;
+    }
+}
+
+fn assemble(name:&str, start:usize, text:&[char], mut elements:Vec<SyntaxElement>) -> SyntaxNode {
+    let end = start + text.len();
+    elements.sort_by(|x:&SyntaxElement, y:&SyntaxElement| x.start().cmp(&y.start()));
+    let mut children = Vec::new();
+    let mut pos = start;
+    for element in elements.into_iter() {
+        // Elements out of the node or overlap others, as results of look ahead, are dropped.
+        if element.start() < pos || element.end() > end {
+            continue;
+        }
+        fill(text, start, pos, element.start(), &mut children);
+        pos = element.end();
+        children.push(element);
+    }
+    fill(text, start, pos, end, &mut children);
+    SyntaxNode{name:String::from(name), start:start, end:end, children:children}
+}
+
+pub fn span<T:'static+Clone, R:'static+Clone>(p:Arc<Parsec<T, R>>) -> Monad<T, (), Spanned<R>> {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<Spanned<R>> {
+        let start = state.pos();
+        match p.parse(state) {
+            Ok(value) => Ok(Spanned{value:value, start:start, end:state.pos()}),
+            Err(err) => Err(err),
+        }
+    }))
+}
+
+pub fn node<R:'static+Clone+IntoElements>(name:&str, p:Arc<Parsec<char, R>>) -> Monad<char, (), SyntaxNode> {
+    let name = String::from(name);
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<SyntaxNode> {
+        let start = state.pos();
+        match p.parse(state) {
+            Ok(value) => {
+                let text = source(state, start);
+                Ok(assemble(name.as_str(), start, &text, value.elements()))
+            }
+            Err(err) => Err(err),
+        }
+    }))
+}
+
+fn leaf<R:'static+Clone>(kind:&str, p:Arc<Parsec<char, R>>, trivia:bool) -> Monad<char, (), SyntaxElement> {
+    let kind = String::from(kind);
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<SyntaxElement> {
+        let start = state.pos();
+        match p.parse(state) {
+            Ok(_) => {
+                let text = source(state, start);
+                let token = token_of(kind.as_str(), start, &text);
+                Ok(if trivia { SyntaxElement::Trivia(token) } else { SyntaxElement::Token(token) })
+            }
+            Err(err) => Err(err),
+        }
+    }))
+}
+
+// The text p matched as a token of kind.
+pub fn token<R:'static+Clone>(kind:&str, p:Arc<Parsec<char, R>>) -> Monad<char, (), SyntaxElement> {
+    leaf(kind, p, false)
+}
+
+// The text p matched as trivia of kind, for comments and other skipped text.
+pub fn trivia<R:'static+Clone>(kind:&str, p:Arc<Parsec<char, R>>) -> Monad<char, (), SyntaxElement> {
+    leaf(kind, p, true)
+}
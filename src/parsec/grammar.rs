@@ -0,0 +1,229 @@
+use parsec::{State, Parsec, Status, M, parser};
+use parsec::atom::{eq, string};
+use parsec::combinator::{either, try, many, many1, optional};
+use parsec::lazy::lazy;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// Runtime support of grammar! macro, every piece of grammar becomes an Arc<Parsec<char, R>>.
+//
+//     grammar! {
+//         number: i64 = [digit()]+ => |ds:Vec<char>| ds.into_iter().collect::<String>().parse().unwrap();
+//         atom: i64 = number | ('(' expr ')' => |(_, x, _):(char, i64, char)| x);
+//         expr: i64 = atom ('+' atom)* => |(x, xs):(i64, Vec<(char, i64)>)| xs.iter().fold(x, |a, &(_, y)| a + y);
+//     }
+//
+// Every rule is `name: Type = expression;` and becomes `pub fn name() -> Arc<Parsec<char, Type>>`.
+// In expressions, 'c' match a char, "text" match a string, [p] is any parsec value, name
+// refer to a rule, (...) groups. Postfix *, + and ? are many, many1 and optional. Items in
+// sequence give a tuple, up to six items. Alternatives separated by | are tried in order with
+// backtracking. `=> f` at the end of a rule or group maps the result of the alternative before
+// it. Results must be Clone and Debug. Rules are resolved when parsing, so they can be
+// recursive, but not left recursive. Long rules may need a larger #![recursion_limit].
+
+pub trait Literal {
+    type Output;
+    fn parsec(self) -> Arc<Parsec<char, Self::Output>>;
+}
+
+impl Literal for char {
+    type Output = char;
+    fn parsec(self) -> Arc<Parsec<char, char>> {
+        arc!(eq(self))
+    }
+}
+
+impl Literal for &'static str {
+    type Output = String;
+    fn parsec(self) -> Arc<Parsec<char, String>> {
+        arc!(string(self))
+    }
+}
+
+pub fn literal<L:Literal>(l:L) -> Arc<Parsec<char, L::Output>> {
+    l.parsec()
+}
+
+pub fn boxed<R, P:'static+Parsec<char, R>>(p:P) -> Arc<Parsec<char, R>> {
+    arc!(p)
+}
+
+// Reference to a rule, the rule function is called at the first parse so rules can refer to
+// each other before they are all built.
+pub fn rule<R:'static>(rule:fn() -> Arc<Parsec<char, R>>) -> Arc<Parsec<char, R>> {
+    arc!(lazy(rule))
+}
+
+pub fn map<R:'static+Clone, P:'static+Clone, F>(p:Arc<Parsec<char, R>>, f:F) -> Arc<Parsec<char, P>>
+where F:'static+Send+Sync+Fn(R)->P {
+    arc!(parser(p).bind(bnd!(move |_:&mut State<char>, x:R|->Status<P> { Ok(f(x)) })))
+}
+
+pub fn seq2<A:'static+Clone, B:'static+Clone>(a:Arc<Parsec<char, A>>, b:Arc<Parsec<char, B>>)
+        -> Arc<Parsec<char, (A, B)>> {
+    arc!(parser(a).bind(bnd!(move |state:&mut State<char>, x:A|->Status<(A, B)> {
+        b.parse(state).map(|y:B| (x, y))
+    })))
+}
+
+pub fn seq3<A:'static+Clone, B:'static+Clone, C:'static+Clone>
+        (a:Arc<Parsec<char, A>>, b:Arc<Parsec<char, B>>, c:Arc<Parsec<char, C>>) -> Arc<Parsec<char, (A, B, C)>> {
+    map(seq2(a, seq2(b, c)), |(x, (y, z)):(A, (B, C))| (x, y, z))
+}
+
+pub fn seq4<A:'static+Clone, B:'static+Clone, C:'static+Clone, D:'static+Clone>
+        (a:Arc<Parsec<char, A>>, b:Arc<Parsec<char, B>>, c:Arc<Parsec<char, C>>, d:Arc<Parsec<char, D>>)
+        -> Arc<Parsec<char, (A, B, C, D)>> {
+    map(seq2(a, seq3(b, c, d)), |(x, (y, z, w)):(A, (B, C, D))| (x, y, z, w))
+}
+
+pub fn seq5<A:'static+Clone, B:'static+Clone, C:'static+Clone, D:'static+Clone, E:'static+Clone>
+        (a:Arc<Parsec<char, A>>, b:Arc<Parsec<char, B>>, c:Arc<Parsec<char, C>>, d:Arc<Parsec<char, D>>,
+         e:Arc<Parsec<char, E>>) -> Arc<Parsec<char, (A, B, C, D, E)>> {
+    map(seq2(a, seq4(b, c, d, e)), |(x, (y, z, w, v)):(A, (B, C, D, E))| (x, y, z, w, v))
+}
+
+pub fn seq6<A:'static+Clone, B:'static+Clone, C:'static+Clone, D:'static+Clone, E:'static+Clone,
+            F:'static+Clone>
+        (a:Arc<Parsec<char, A>>, b:Arc<Parsec<char, B>>, c:Arc<Parsec<char, C>>, d:Arc<Parsec<char, D>>,
+         e:Arc<Parsec<char, E>>, f:Arc<Parsec<char, F>>) -> Arc<Parsec<char, (A, B, C, D, E, F)>> {
+    map(seq2(a, seq5(b, c, d, e, f)), |(x, (y, z, w, v, u)):(A, (B, C, D, E, F))| (x, y, z, w, v, u))
+}
+
+// Try alternatives in order, the last one report the error if all failed.
+pub fn choice<R:'static+Clone>(mut alternatives:Vec<Arc<Parsec<char, R>>>) -> Arc<Parsec<char, R>> {
+    let mut re = alternatives.pop().expect("choice need at least one alternative");
+    while let Some(alt) = alternatives.pop() {
+        re = arc!(either(arc!(try(alt)), re));
+    }
+    re
+}
+
+pub fn star<R:'static+Clone+Debug+Send+Sync>(p:Arc<Parsec<char, R>>) -> Arc<Parsec<char, Vec<R>>> {
+    arc!(many(p))
+}
+
+pub fn plus<R:'static+Clone+Debug+Send+Sync>(p:Arc<Parsec<char, R>>) -> Arc<Parsec<char, Vec<R>>> {
+    arc!(many1(p))
+}
+
+pub fn opt<R:'static+Clone+Debug+Send+Sync>(p:Arc<Parsec<char, R>>) -> Arc<Parsec<char, Option<R>>> {
+    arc!(optional(p))
+}
+
+#[macro_export]
+macro_rules! grammar {
+    () => ();
+    (@rule $name:ident, $t:ty, [$($body:tt)*] ; $($rest:tt)*) => (
+        pub fn $name() -> ::std::sync::Arc<$crate::parsec::Parsec<char, $t>> {
+            __grammar_alt!($($body)*)
+        }
+        grammar!($($rest)*);
+    );
+    (@rule $name:ident, $t:ty, [$($body:tt)*] $next:tt $($rest:tt)*) => (
+        grammar!(@rule $name, $t, [$($body)* $next] $($rest)*);
+    );
+    (@rule $name:ident, $t:ty, [$($body:tt)*]) => (
+        __grammar_error!("grammar!: missing ';' after rule", $name);
+    );
+    ($name:ident : $t:ty = $($rest:tt)*) => (
+        grammar!(@rule $name, $t, [] $($rest)*);
+    );
+    ($($other:tt)*) => (
+        __grammar_error!("grammar!: expect `name: Type = expression;` but got", $($other)*);
+    );
+}
+
+// Errors of grammar! macro. It has no rules, so an invocation fails to expand and the compiler
+// shows the message literal as the unexpected token; compile_error! is not in this toolchain.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __grammar_error {
+    () => ();
+}
+
+// Alternatives of grammar! expression. The state is alternatives done, items of current
+// sequence before the pending item, and the pending item which postfix operators apply to.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __grammar_alt {
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr}) => (
+        $crate::parsec::grammar::choice(vec![$($alt,)* __grammar_seq!($($seq,)* $p)])
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {}) => (
+        __grammar_error!("grammar!: empty alternative")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} => $f:expr) => (
+        $crate::parsec::grammar::choice(vec![$($alt,)*
+            $crate::parsec::grammar::map(__grammar_seq!($($seq,)* $p), $f)])
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} => $($rest:tt)*) => (
+        __grammar_error!("grammar!: action without expression")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} | $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)* __grammar_seq!($($seq,)* $p),] [] {} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} | $($rest:tt)*) => (
+        __grammar_error!("grammar!: empty alternative")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} * $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::star($p)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} + $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::plus($p)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} ? $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::opt($p)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} * $($rest:tt)*) => (
+        __grammar_error!("grammar!: postfix operator * without operand")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} + $($rest:tt)*) => (
+        __grammar_error!("grammar!: postfix operator + without operand")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} ? $($rest:tt)*) => (
+        __grammar_error!("grammar!: postfix operator ? without operand")
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} ($($group:tt)*) $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)* $p,] {__grammar_alt!($($group)*)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} ($($group:tt)*) $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {__grammar_alt!($($group)*)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} [$e:expr] $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)* $p,] {$crate::parsec::grammar::boxed($e)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} [$e:expr] $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::boxed($e)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} $name:ident $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)* $p,] {$crate::parsec::grammar::rule($name)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} $name:ident $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::rule($name)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {$p:expr} $l:tt $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)* $p,] {$crate::parsec::grammar::literal($l)} $($rest)*)
+    );
+    (@munch [$($alt:expr,)*] [$($seq:expr,)*] {} $l:tt $($rest:tt)*) => (
+        __grammar_alt!(@munch [$($alt,)*] [$($seq,)*] {$crate::parsec::grammar::literal($l)} $($rest)*)
+    );
+    (@munch $($other:tt)*) => (
+        __grammar_error!("grammar!: unexpected", $($other)*)
+    );
+    ($($tokens:tt)*) => (
+        __grammar_alt!(@munch [] [] {} $($tokens)*)
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __grammar_seq {
+    ($a:expr) => ($a);
+    ($a:expr, $b:expr) => ($crate::parsec::grammar::seq2($a, $b));
+    ($a:expr, $b:expr, $c:expr) => ($crate::parsec::grammar::seq3($a, $b, $c));
+    ($a:expr, $b:expr, $c:expr, $d:expr) => ($crate::parsec::grammar::seq4($a, $b, $c, $d));
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr) => ($crate::parsec::grammar::seq5($a, $b, $c, $d, $e));
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr) => ($crate::parsec::grammar::seq6($a, $b, $c, $d, $e, $f));
+    ($($other:expr),*) => (__grammar_error!("grammar!: sequence longer than six items, group it with parentheses"));
+}
@@ -0,0 +1,214 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, M};
+use parsec::atom::pack;
+use parsec::cst::{SyntaxNode, SyntaxElement, SyntaxToken, IntoElements, node};
+use std::collections::HashMap;
+use std::cmp::max;
+use std::mem;
+use std::iter::FromIterator;
+use std::sync::{Arc, RwLock};
+
+// Incremental reparsing for editors. Rules built by Reparser::rule keep their syntax nodes by
+// rule name and start position, with the end of text every run of the rule read. After an
+// edit, a node read nothing in the edited range is reused, shifted if it is after the edit,
+// and only rules cover the edit run again. So the rules must be context free, their result
+// depends only on the text they read, and the parsec must be run by Reparser.
+
+// Replace chars from start to end by text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(start:usize, end:usize, text:&str) -> Edit {
+        Edit{start:start, end:end, text:String::from(text)}
+    }
+
+    // Edited text, or an error if the range is not in text.
+    pub fn apply(&self, text:&str) -> Status<String> {
+        let chars:Vec<char> = text.chars().collect();
+        if self.start > self.end || self.end > chars.len() {
+            let message = format!("edit {}..{} out of text length {}", self.start, self.end, chars.len());
+            return Err(SimpleError::new(self.start, message));
+        }
+        let mut re:String = chars[..self.start].iter().cloned().collect();
+        re.push_str(self.text.as_str());
+        re.extend(chars[self.end..].iter().cloned());
+        Ok(re)
+    }
+
+    fn delta(&self) -> isize {
+        self.text.chars().count() as isize - (self.end - self.start) as isize
+    }
+}
+
+// Count of rule results reused and rules run in the last parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub reused: usize,
+    pub parsed: usize,
+}
+
+struct Entry {
+    node: SyntaxNode,
+    end: usize,
+    examined: usize,
+}
+
+struct Memo {
+    text: String,
+    entries: HashMap<(String, usize), Entry>,
+    furthest: usize,
+    stats: Stats,
+}
+
+// VecState records the furthest position read for the memo.
+struct TrackedState {
+    inner: VecState<char>,
+    memo: Arc<RwLock<Memo>>,
+}
+
+impl TrackedState {
+    fn read(&self) {
+        let pos = self.inner.pos() + 1;
+        let mut memo = self.memo.write().unwrap();
+        memo.furthest = max(memo.furthest, pos);
+    }
+}
+
+impl State<char> for TrackedState {
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+    fn seek_to(&mut self, to:usize) -> bool {
+        self.inner.seek_to(to)
+    }
+    fn next(&mut self) -> Option<char> {
+        self.read();
+        self.inner.next()
+    }
+    fn next_by(&mut self, pred:&Fn(&char)->bool) -> Status<char> {
+        self.read();
+        self.inner.next_by(pred)
+    }
+    fn indents(&mut self) -> Option<&mut Vec<usize>> {
+        self.inner.indents()
+    }
+}
+
+fn moved(pos:usize, delta:isize) -> usize {
+    (pos as isize + delta) as usize
+}
+
+fn shift_token(token:&SyntaxToken, delta:isize) -> SyntaxToken {
+    SyntaxToken{kind:token.kind.clone(), start:moved(token.start, delta), end:moved(token.end, delta),
+                text:token.text.clone()}
+}
+
+fn shift(node:&SyntaxNode, delta:isize) -> SyntaxNode {
+    let children = node.children.iter().map(|child:&SyntaxElement| match *child {
+        SyntaxElement::Node(ref n) => SyntaxElement::Node(shift(n, delta)),
+        SyntaxElement::Token(ref t) => SyntaxElement::Token(shift_token(t, delta)),
+        SyntaxElement::Trivia(ref t) => SyntaxElement::Trivia(shift_token(t, delta)),
+    }).collect();
+    SyntaxNode{name:node.name.clone(), start:moved(node.start, delta), end:moved(node.end, delta),
+               children:children}
+}
+
+#[derive(Clone)]
+pub struct Reparser {
+    memo: Arc<RwLock<Memo>>,
+}
+
+impl Reparser {
+    pub fn new() -> Reparser {
+        let memo = Memo{text:String::new(), entries:HashMap::new(), furthest:0, stats:Stats{reused:0, parsed:0}};
+        Reparser{memo:Arc::new(RwLock::new(memo))}
+    }
+
+    // A node of name from p, memorized for later parses.
+    pub fn rule<R:'static+Clone+IntoElements>(&self, name:&str, p:Arc<Parsec<char, R>>) -> Arc<Parsec<char, SyntaxNode>> {
+        let memo = self.memo.clone();
+        let name = String::from(name);
+        let body = node(name.as_str(), p);
+        arc!(pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<SyntaxNode> {
+            let start = state.pos();
+            let key = (name.clone(), start);
+            let hit = memo.read().unwrap().entries.get(&key)
+                .map(|e:&Entry| (e.node.clone(), e.end, e.examined));
+            if let Some((node, end, examined)) = hit {
+                let mut memo = memo.write().unwrap();
+                memo.stats.reused += 1;
+                memo.furthest = max(memo.furthest, examined);
+                state.seek_to(end);
+                return Ok(node);
+            }
+            let outer = {
+                let mut memo = memo.write().unwrap();
+                mem::replace(&mut memo.furthest, start)
+            };
+            let re = body.parse(state);
+            let mut memo = memo.write().unwrap();
+            let examined = max(memo.furthest, state.pos());
+            memo.furthest = max(outer, examined);
+            memo.stats.parsed += 1;
+            if let Ok(ref node) = re {
+                memo.entries.insert(key, Entry{node:node.clone(), end:state.pos(), examined:examined});
+            }
+            re
+        })))
+    }
+
+    fn run<R>(&self, p:&Parsec<char, R>) -> Status<R> {
+        let text = {
+            let mut memo = self.memo.write().unwrap();
+            memo.furthest = 0;
+            memo.stats = Stats{reused:0, parsed:0};
+            memo.text.clone()
+        };
+        let mut state = TrackedState{inner:VecState::from_iter(text.chars()), memo:self.memo.clone()};
+        p.parse(&mut state)
+    }
+
+    // Parse text from scratch, results of earlier parses are dropped.
+    pub fn parse<R>(&self, p:&Parsec<char, R>, text:&str) -> Status<R> {
+        {
+            let mut memo = self.memo.write().unwrap();
+            memo.text = String::from(text);
+            memo.entries.clear();
+        }
+        self.run(p)
+    }
+
+    // Apply the edit to the text of last parse and parse it again, reuse the nodes the edit
+    // don't touch.
+    pub fn reparse<R>(&self, p:&Parsec<char, R>, edit:&Edit) -> Status<R> {
+        {
+            let mut memo = self.memo.write().unwrap();
+            let text = try!(edit.apply(memo.text.as_str()));
+            let delta = edit.delta();
+            let entries = mem::replace(&mut memo.entries, HashMap::new());
+            for ((name, start), entry) in entries.into_iter() {
+                if entry.examined <= edit.start {
+                    memo.entries.insert((name, start), entry);
+                } else if start >= edit.end {
+                    let entry = Entry{node:shift(&entry.node, delta), end:moved(entry.end, delta),
+                                      examined:moved(entry.examined, delta)};
+                    memo.entries.insert((name, moved(start, delta)), entry);
+                }
+            }
+            memo.text = text;
+        }
+        self.run(p)
+    }
+
+    pub fn text(&self) -> String {
+        self.memo.read().unwrap().text.clone()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.memo.read().unwrap().stats.clone()
+    }
+}
@@ -0,0 +1,216 @@
+use parsec::{State, SimpleError, Parsec, Status, Monad, M};
+use parsec::atom::pack;
+use std::sync::Arc;
+
+// Indentation sensitive parsers keep a stack of reference positions in the state, with_pos
+// push current position and the checks compare current column with the top of it.
+// Columns count chars from 1, a tab is one column.
+
+// Column of pos, it scans back to the line start, or to the first position the state can seek
+// to, as the start of a chunk.
+pub fn column(state:&mut State<char>, pos:usize) -> usize {
+    let current = state.pos();
+    let mut col = 1;
+    let mut idx = pos;
+    while idx > 0 && state.seek_to(idx - 1) {
+        if state.next() == Some('\n') {
+            break;
+        }
+        col += 1;
+        idx -= 1;
+    }
+    state.seek_to(current);
+    col
+}
+
+// Line of pos, counted back to the first position the state can seek to. It scans all text
+// before pos, so parsers only call it for error messages.
+pub fn line(state:&mut State<char>, pos:usize) -> usize {
+    let current = state.pos();
+    let mut re = 1;
+    let mut idx = pos;
+    while idx > 0 && state.seek_to(idx - 1) {
+        if state.next() == Some('\n') {
+            re += 1;
+        }
+        idx -= 1;
+    }
+    state.seek_to(current);
+    re
+}
+
+// True if a newline is between from and to, it only reads the text between them.
+fn crosses_line(state:&mut State<char>, from:usize, to:usize) -> bool {
+    let current = state.pos();
+    let (from, to) = if from <= to { (from, to) } else { (to, from) };
+    let mut re = false;
+    if state.seek_to(from) {
+        while state.pos() < to {
+            match state.next() {
+                Some('\n') => {
+                    re = true;
+                    break;
+                }
+                Some(_) => (),
+                None => break,
+            }
+        }
+    }
+    state.seek_to(current);
+    re
+}
+
+fn reference(state:&mut State<char>) -> Status<usize> {
+    let pos = state.pos();
+    match state.indents().and_then(|stack:&mut Vec<usize>| stack.last().cloned()) {
+        Some(re) => Ok(re),
+        None => {
+            let message = format!("no indentation reference at {}, use with_pos or block first", pos);
+            Err(SimpleError::new(pos, message))
+        }
+    }
+}
+
+fn indentation_error(state:&mut State<char>, what:&str, expect:&str, ref_col:usize) -> SimpleError {
+    let pos = state.pos();
+    let message = format!("{} at line {} column {}, expect column {}{}",
+                          what, line(state, pos), column(state, pos), expect, ref_col);
+    SimpleError::new(pos, message)
+}
+
+fn at_eof(state:&mut State<char>) -> bool {
+    let pos = state.pos();
+    let re = state.next().is_none();
+    state.seek_to(pos);
+    re
+}
+
+// Run parsec with current position as indentation reference. The stack is cut back to its depth
+// before the parse whether it success or not, so a failed alternative leaves no reference.
+pub fn with_pos<R:'static+Clone>(p:Arc<Parsec<char, R>>) -> Monad<char, (), R> {
+    pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<R> {
+        let pos = state.pos();
+        let depth = match state.indents() {
+            Some(stack) => {
+                let depth = stack.len();
+                stack.push(pos);
+                depth
+            }
+            None => {
+                let message = format!("state at {} don't keep indentation", pos);
+                return Err(SimpleError::new(pos, message));
+            }
+        };
+        let re = p.parse(state);
+        state.indents().map(|stack:&mut Vec<usize>| stack.truncate(depth));
+        re
+    }))
+}
+
+// Current column must be same as the reference.
+pub fn check_indent() -> Monad<char, (), ()> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<()> {
+        reference(state).and_then(|reference:usize| {
+            let ref_col = column(state, reference);
+            let pos = state.pos();
+            let col = column(state, pos);
+            if col == ref_col {
+                Ok(())
+            } else if col > ref_col {
+                Err(indentation_error(state, "unexpected indentation", "", ref_col))
+            } else {
+                Err(indentation_error(state, "unexpected dedent", "", ref_col))
+            }
+        })
+    }))
+}
+
+// Current column must be greater than the reference.
+pub fn indented() -> Monad<char, (), ()> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<()> {
+        reference(state).and_then(|reference:usize| {
+            let ref_col = column(state, reference);
+            let pos = state.pos();
+            if column(state, pos) > ref_col {
+                Ok(())
+            } else {
+                Err(indentation_error(state, "expect indentation", "greater than ", ref_col))
+            }
+        })
+    }))
+}
+
+// Current position must be at the same line as the reference.
+pub fn same_line() -> Monad<char, (), ()> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<()> {
+        reference(state).and_then(|reference:usize| {
+            let pos = state.pos();
+            if !crosses_line(state, reference, pos) {
+                Ok(())
+            } else {
+                let message = format!("expect same line as {} at {}", reference, pos);
+                Err(SimpleError::new(pos, message))
+            }
+        })
+    }))
+}
+
+// Continue a line or indent it, as expression spread across lines.
+pub fn same_or_indented() -> Monad<char, (), ()> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<()> {
+        let pos = state.pos();
+        let re = same_line().parse(state);
+        if re.is_ok() {
+            re
+        } else {
+            state.seek_to(pos);
+            indented().parse(state)
+        }
+    }))
+}
+
+// One or more items aligned at column of the first one. Items should consume their trailing
+// white spaces and newlines, the block stop at a dedent line or eof, and a line indented
+// deeper than the block is an error.
+pub fn block<R:'static+Clone>(p:Arc<Parsec<char, R>>) -> Monad<char, (), Vec<R>> {
+    let items = pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Vec<R>> {
+        let mut re = Vec::new();
+        loop {
+            if re.len() > 0 {
+                if at_eof(state) {
+                    return Ok(re);
+                }
+                let pos = state.pos();
+                let check = check_indent().parse(state);
+                if check.is_err() {
+                    let ref_pos = try!(reference(state));
+                    if column(state, pos) < column(state, ref_pos) {
+                        return Ok(re);
+                    }
+                    return Err(check.unwrap_err());
+                }
+            }
+            match p.parse(state) {
+                Ok(x) => re.push(x),
+                Err(err) => return Err(err),
+            }
+        }
+    }));
+    with_pos(arc!(items))
+}
+
+// A header and an optional block indented deeper than the header, like python suite or yaml
+// mapping. Header should consume its trailing newline and spaces before the block.
+pub fn indent_block<H:'static+Clone, R:'static+Clone>(header:Arc<Parsec<char, H>>, item:Arc<Parsec<char, R>>)
+        -> Monad<char, (), (H, Vec<R>)> {
+    let body = pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<(H, Vec<R>)> {
+        let head = try!(header.parse(state));
+        let pos = state.pos();
+        if at_eof(state) || indented().parse(state).is_err() {
+            state.seek_to(pos);
+            return Ok((head, Vec::new()));
+        }
+        block(item.clone()).parse(state).map(|items:Vec<R>| (head, items))
+    }));
+    with_pos(arc!(body))
+}
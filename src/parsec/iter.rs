@@ -0,0 +1,75 @@
+use parsec::{State, SimpleError, Parsec, Status, M, parser};
+use parsec::atom::pack;
+use std::sync::Arc;
+
+// Run a parsec over the state again and again, one result for each record until the end of
+// input. An error stops the iterator, unless a resync parsec is given to skip the bad record,
+// it runs from the start of the record failed.
+pub struct ParseIter<'a, T:'a, R> {
+    parsec: Arc<Parsec<T, R>>,
+    state: &'a mut State<T>,
+    resync: Option<Arc<Parsec<T, ()>>>,
+    done: bool,
+}
+
+impl<'a, T:'static+Clone, R> ParseIter<'a, T, R> {
+    pub fn new(p:Arc<Parsec<T, R>>, state:&'a mut State<T>) -> ParseIter<'a, T, R> {
+        ParseIter{parsec:p, state:state, resync:None, done:false}
+    }
+
+    pub fn resync<S:'static+Clone>(mut self, skip:Arc<Parsec<T, S>>) -> ParseIter<'a, T, R> {
+        self.resync = Some(arc!(parser(skip).then(arc!(pack(())))));
+        self
+    }
+
+    fn at_eof(&mut self) -> bool {
+        let pos = self.state.pos();
+        if self.state.next().is_none() {
+            return true;
+        }
+        self.state.seek_to(pos);
+        false
+    }
+
+    // Skip the record failed at pos, false if the resync parsec can't move forward.
+    fn skip(&mut self, pos:usize) -> bool {
+        match self.resync {
+            Some(ref skip) => {
+                self.state.seek_to(pos);
+                skip.parse(self.state).is_ok() && self.state.pos() > pos
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'a, T:'static+Clone, R> Iterator for ParseIter<'a, T, R> {
+    type Item = Status<R>;
+
+    fn next(&mut self) -> Option<Status<R>> {
+        if self.done || self.at_eof() {
+            self.done = true;
+            return None;
+        }
+        let pos = self.state.pos();
+        match self.parsec.parse(self.state) {
+            Ok(_) if self.state.pos() == pos => {
+                // A parsec consume nothing would give the same result forever.
+                self.done = true;
+                let message = format!("record parsec consume nothing at {}", pos);
+                Some(Err(SimpleError::new(pos, message)))
+            }
+            Ok(x) => Some(Ok(x)),
+            Err(err) => {
+                if !self.skip(pos) {
+                    self.done = true;
+                }
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+pub fn parse_iter<'a, T:'static+Clone, R>(p:Arc<Parsec<T, R>>, state:&'a mut State<T>) -> ParseIter<'a, T, R> {
+    ParseIter::new(p, state)
+}
@@ -0,0 +1,161 @@
+use parsec::{State, SimpleError, Parsec, Status, M};
+use std::sync::{Arc, RwLock};
+use std::fmt::{Debug, Formatter};
+use std::fmt;
+
+// Build the parsec at the first parse and keep it, so a rule can refer to itself or to rules
+// built after it.
+pub struct Lazy<T, R> {
+    build: Arc<Box<Fn()->Arc<Parsec<T, R>>+Send+Sync>>,
+    cache: Arc<RwLock<Option<Arc<Parsec<T, R>>>>>,
+}
+
+impl<T, R> Lazy<T, R> {
+    pub fn new(build:Arc<Box<Fn()->Arc<Parsec<T, R>>+Send+Sync>>) -> Lazy<T, R> {
+        Lazy{build:build, cache:Arc::new(RwLock::new(None))}
+    }
+
+    fn get(&self) -> Arc<Parsec<T, R>> {
+        if let Some(ref p) = *self.cache.read().unwrap() {
+            return p.clone();
+        }
+        // Build without the lock, the builder may create other lazy parsecs.
+        let p = (self.build)();
+        let mut cache = self.cache.write().unwrap();
+        if cache.is_none() {
+            *cache = Some(p);
+        }
+        cache.as_ref().unwrap().clone()
+    }
+}
+
+impl<T, R> Parsec<T, R> for Lazy<T, R> {
+    fn parse(&self, state:&mut State<T>)->Status<R> {
+        self.get().parse(state)
+    }
+}
+
+impl<'a, T, R> FnOnce<(&'a mut State<T>, )> for Lazy<T, R> {
+    type Output = Status<R>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<R> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T, R> FnMut<(&'a mut State<T>, )> for Lazy<T, R> {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<R> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T, R> Fn<(&'a mut State<T>, )> for Lazy<T, R> {
+    extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<R> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<T, R> Clone for Lazy<T, R> {
+    fn clone(&self)->Self {
+        Lazy{build:self.build.clone(), cache:self.cache.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.build = source.build.clone();
+        self.cache = source.cache.clone();
+    }
+}
+
+impl<T, R> Debug for Lazy<T, R> {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        "<lazy parsec>".fmt(formatter)
+    }
+}
+
+impl<T:'static+Clone, R:'static+Clone> M<T, R> for Lazy<T, R>{}
+
+pub fn lazy<T, R, F>(build:F) -> Lazy<T, R> where F:'static+Send+Sync+Fn()->Arc<Parsec<T, R>> {
+    Lazy::new(Arc::new(Box::new(build)))
+}
+
+// A parsec declared before it is defined, clones share the same definition. Define it by set
+// after the parsecs refer to it are built. The definition usually refer back to the reference,
+// that cycle of Arc is never freed, so declare a grammar once and reuse it.
+pub struct ParserRef<T, R> {
+    cell: Arc<RwLock<Option<Arc<Parsec<T, R>>>>>,
+}
+
+impl<T, R> ParserRef<T, R> {
+    pub fn new() -> ParserRef<T, R> {
+        ParserRef{cell:Arc::new(RwLock::new(None))}
+    }
+
+    // Define or replace the parsec.
+    pub fn set(&self, p:Arc<Parsec<T, R>>) {
+        *self.cell.write().unwrap() = Some(p);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.cell.read().unwrap().is_some()
+    }
+}
+
+impl<T, R> Parsec<T, R> for ParserRef<T, R> {
+    fn parse(&self, state:&mut State<T>)->Status<R> {
+        let p = self.cell.read().unwrap().clone();
+        match p {
+            Some(p) => p.parse(state),
+            None => {
+                let pos = state.pos();
+                let message = format!("parser reference used at {} before it is defined", pos);
+                Err(SimpleError::new(pos, message))
+            }
+        }
+    }
+}
+
+impl<'a, T, R> FnOnce<(&'a mut State<T>, )> for ParserRef<T, R> {
+    type Output = Status<R>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<T>, )) -> Status<R> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T, R> FnMut<(&'a mut State<T>, )> for ParserRef<T, R> {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<T>, )) -> Status<R> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, T, R> Fn<(&'a mut State<T>, )> for ParserRef<T, R> {
+    extern "rust-call" fn call(&self, args: (&'a mut State<T>, )) -> Status<R> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<T, R> Clone for ParserRef<T, R> {
+    fn clone(&self)->Self {
+        ParserRef{cell:self.cell.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.cell = source.cell.clone();
+    }
+}
+
+impl<T, R> Debug for ParserRef<T, R> {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        if self.is_set() {
+            "<parser reference>".fmt(formatter)
+        } else {
+            "<undefined parser reference>".fmt(formatter)
+        }
+    }
+}
+
+impl<T:'static+Clone, R:'static+Clone> M<T, R> for ParserRef<T, R>{}
+
+pub fn forward_declare<T, R>() -> ParserRef<T, R> {
+    ParserRef::new()
+}
@@ -0,0 +1,67 @@
+use parsec::{Parsec, Monad, M, parser};
+use parsec::atom::{pack, string};
+use parsec::combinator::{Either, Skip, either, try, skip_many};
+use parsec::text::{line_comment, block_comment, white_space};
+use std::sync::Arc;
+
+// Lexeme share the white space parser, it skip spaces and comments after every token.
+#[derive(Debug, Clone)]
+pub struct Lexeme {
+    skippers: Vec<Arc<Parsec<char, ()>>>,
+}
+
+impl Lexeme {
+    pub fn new() -> Lexeme {
+        let space = arc!(white_space().then(arc!(pack(()))));
+        Lexeme{skippers:vec![space]}
+    }
+
+    // Replace the default white space, it should consume at least one char when success.
+    pub fn white<R:'static+Clone>(p:Arc<Parsec<char, R>>) -> Lexeme {
+        Lexeme{skippers:vec![arc!(parser(p).then(arc!(pack(()))))]}
+    }
+
+    pub fn skip<R:'static+Clone>(mut self, p:Arc<Parsec<char, R>>) -> Lexeme {
+        self.skippers.push(arc!(parser(p).then(arc!(pack(())))));
+        self
+    }
+
+    pub fn line_comment(self, prefix:&str) -> Lexeme {
+        self.skip(arc!(line_comment(prefix)))
+    }
+
+    pub fn block_comment(self, open:&str, close:&str, nested:bool) -> Lexeme {
+        self.skip(arc!(block_comment(open, close, nested)))
+    }
+
+    pub fn white_space(&self) -> Skip<char, ()> {
+        let mut skippers = self.skippers.iter();
+        let first:Arc<Parsec<char, ()>> = arc!(try(skippers.next().unwrap().clone()));
+        let any = skippers.fold(first, |acc:Arc<Parsec<char, ()>>, p:&Arc<Parsec<char, ()>>| {
+            let alt:Either<char, ()> = either(acc, arc!(try(p.clone())));
+            arc!(alt)
+        });
+        skip_many(any)
+    }
+
+    pub fn lexeme<R:'static+Clone>(&self, p:Arc<Parsec<char, R>>) -> Monad<char, R, R> {
+        parser(p).over(arc!(self.white_space()))
+    }
+
+    pub fn symbol(&self, text:&str) -> Monad<char, String, String> {
+        string(text).over(arc!(self.white_space()))
+    }
+
+    // Skip leading white space then run p, for the beginning of input.
+    pub fn whole<R:'static+Clone>(&self, p:Arc<Parsec<char, R>>) -> Monad<char, Vec<()>, R> {
+        self.white_space().then(p)
+    }
+}
+
+pub fn lexeme<R:'static+Clone>(p:Arc<Parsec<char, R>>) -> Monad<char, R, R> {
+    Lexeme::new().lexeme(p)
+}
+
+pub fn symbol(text:&str) -> Monad<char, String, String> {
+    Lexeme::new().symbol(text)
+}
@@ -0,0 +1,268 @@
+use parsec::{State, VecState, SimpleError, Parsec, Status, M, parser};
+use parsec::atom::pack;
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt;
+use std::sync::Arc;
+
+// A token produced by the lexer, start and end are char positions in the source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<K> {
+    pub kind: K,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl<K> Token<K> {
+    pub fn new(kind:K, text:String, start:usize, end:usize) -> Token<K> {
+        Token{kind:kind, text:text, start:start, end:end}
+    }
+}
+
+impl<K> Display for Token<K> where K:Debug {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "{:?}({:?})", self.kind, self.text)
+    }
+}
+
+// Lexer turn text into tokens by rules, every rule is a parsec over chars.
+// The longest match wins, if some rules match same length, the first declared one wins.
+pub struct Lexer<K> {
+    rules: Vec<(K, Arc<Parsec<char, ()>>)>,
+    skips: Vec<Arc<Parsec<char, ()>>>,
+}
+
+impl<K> Lexer<K> where K:Clone {
+    pub fn new() -> Lexer<K> {
+        Lexer{rules:Vec::new(), skips:Vec::new()}
+    }
+
+    pub fn rule<R:'static+Clone>(mut self, kind:K, p:Arc<Parsec<char, R>>) -> Lexer<K> {
+        self.rules.push((kind, arc!(parser(p).then(arc!(pack(()))))));
+        self
+    }
+
+    // Text matched by skip rules (white spaces, comments) between tokens is dropped.
+    pub fn skip<R:'static+Clone>(mut self, p:Arc<Parsec<char, R>>) -> Lexer<K> {
+        self.skips.push(arc!(parser(p).then(arc!(pack(())))));
+        self
+    }
+
+    pub fn tokenize(&self, source:&str) -> Status<Vec<Token<K>>> {
+        let mut state:VecState<char> = source.chars().collect();
+        self.tokenize_state(&mut state)
+    }
+
+    pub fn tokenize_state(&self, state:&mut State<char>) -> Status<Vec<Token<K>>> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_ignored(state);
+            let start = state.pos();
+            let head = state.next();
+            if head.is_none() {
+                return Ok(tokens);
+            }
+            let mut best:Option<(usize, usize)> = None;
+            for (idx, &(_, ref rule)) in self.rules.iter().enumerate() {
+                state.seek_to(start);
+                if rule.parse(state).is_ok() {
+                    let end = state.pos();
+                    if end > start && best.map_or(true, |(_, e)| end > e) {
+                        best = Some((idx, end));
+                    }
+                }
+            }
+            state.seek_to(start);
+            match best {
+                Some((idx, end)) => {
+                    let mut text = String::new();
+                    while state.pos() < end {
+                        match state.next() {
+                            Some(c) => text.push(c),
+                            None => break,
+                        }
+                    }
+                    let kind = self.rules[idx].0.clone();
+                    tokens.push(Token::new(kind, text, start, end));
+                }
+                None => {
+                    let message = format!("unexpected {:?} at {}, no token rule matched", head.unwrap(), start);
+                    return Err(SimpleError::new(start, message));
+                }
+            }
+        }
+    }
+
+    fn skip_ignored(&self, state:&mut State<char>) {
+        loop {
+            let mut progress = false;
+            for skip in self.skips.iter() {
+                let pos = state.pos();
+                if skip.parse(state).is_ok() && state.pos() > pos {
+                    progress = true;
+                } else {
+                    state.seek_to(pos);
+                }
+            }
+            if !progress {
+                return;
+            }
+        }
+    }
+}
+
+impl<K> Clone for Lexer<K> where K:Clone {
+    fn clone(&self)->Self {
+        Lexer{rules:self.rules.clone(), skips:self.skips.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.rules = source.rules.clone();
+        self.skips = source.skips.clone();
+    }
+}
+
+impl<K> Debug for Lexer<K> where K:Debug {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        let kinds = self.rules.iter().map(|&(ref k, _)| k).collect::<Vec<&K>>();
+        write!(formatter, "<lexer {:?}>", kinds)
+    }
+}
+
+// Token atoms report errors at source position of the token instead of index of token stream.
+fn expect_token<K>(state:&mut State<Token<K>>, pred:&Fn(&Token<K>)->bool, label:&str)->Status<Token<K>>
+where K:Clone+Debug {
+    let pos = state.pos();
+    match state.next() {
+        Some(tok) => {
+            if pred(&tok) {
+                Ok(tok)
+            } else {
+                state.seek_to(pos);
+                let message = format!("expect {} at {} but got {}", label, tok.start, tok);
+                Err(SimpleError::new(tok.start, message))
+            }
+        }
+        None => {
+            let end = source_end(state, pos);
+            let message = format!("expect {} at {} but got eof", label, end);
+            Err(SimpleError::new(end, message))
+        }
+    }
+}
+
+// Source position after last token, read it from the previous token and leave state unchanged.
+fn source_end<K>(state:&mut State<Token<K>>, pos:usize)->usize where K:Clone {
+    if pos == 0 {
+        return 0;
+    }
+    state.seek_to(pos - 1);
+    state.next().map_or(0, |tok:Token<K>| tok.end)
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenKind<K> {
+    kind: K,
+}
+
+impl<K> TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    fn new(kind:K) -> TokenKind<K> {
+        TokenKind{kind:kind}
+    }
+}
+
+impl<K> Parsec<Token<K>, Token<K>> for TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    fn parse(&self, state:&mut State<Token<K>>)->Status<Token<K>> {
+        let ref kind = self.kind;
+        let label = format!("{:?}", kind);
+        expect_token(state, &|tok:&Token<K>| tok.kind.eq(kind), label.as_str())
+    }
+}
+
+impl<'a, K> FnOnce<(&'a mut State<Token<K>>, )> for TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    type Output = Status<Token<K>>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, K> FnMut<(&'a mut State<Token<K>>, )> for TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, K> Fn<(&'a mut State<Token<K>>, )> for TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    extern "rust-call" fn call(&self, args: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<K:'static+Eq+Clone+Debug+Send+Sync> M<Token<K>, Token<K>> for TokenKind<K>{}
+
+pub fn token_kind<K>(kind:K) -> TokenKind<K> where K:Eq+Clone+Debug+Send+Sync {
+    TokenKind::new(kind)
+}
+
+pub struct SatisfyToken<K> {
+    pred: Arc<Box<Fn(&Token<K>)->bool+Send+Sync>>,
+    label: Arc<String>,
+}
+
+impl<K> SatisfyToken<K> where K:Clone+Debug {
+    fn new(pred:Arc<Box<Fn(&Token<K>)->bool+Send+Sync>>, label:String) -> SatisfyToken<K> {
+        SatisfyToken{pred:pred.clone(), label:Arc::new(label)}
+    }
+}
+
+impl<K> Parsec<Token<K>, Token<K>> for SatisfyToken<K> where K:Clone+Debug {
+    fn parse(&self, state:&mut State<Token<K>>)->Status<Token<K>> {
+        let pred = self.pred.clone();
+        expect_token(state, &|tok:&Token<K>| (pred)(tok), self.label.as_str())
+    }
+}
+
+impl<'a, K> FnOnce<(&'a mut State<Token<K>>, )> for SatisfyToken<K> where K:Clone+Debug {
+    type Output = Status<Token<K>>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, K> FnMut<(&'a mut State<Token<K>>, )> for SatisfyToken<K> where K:Clone+Debug {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a, K> Fn<(&'a mut State<Token<K>>, )> for SatisfyToken<K> where K:Clone+Debug {
+    extern "rust-call" fn call(&self, args: (&'a mut State<Token<K>>, )) -> Status<Token<K>> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl<K> Clone for SatisfyToken<K> where K:Clone+Debug {
+    fn clone(&self)->Self {
+        SatisfyToken{pred:self.pred.clone(), label:self.label.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.pred = source.pred.clone();
+        self.label = source.label.clone();
+    }
+}
+
+impl<K> Debug for SatisfyToken<K> where K:Clone+Debug {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "<satisfy token parsec: {}>", self.label)
+    }
+}
+
+impl<K:'static+Clone+Debug> M<Token<K>, Token<K>> for SatisfyToken<K>{}
+
+pub fn satisfy_token<K>(pred:Arc<Box<Fn(&Token<K>)->bool+Send+Sync>>, label:String) -> SatisfyToken<K>
+where K:Clone+Debug {
+    SatisfyToken::new(pred, label)
+}
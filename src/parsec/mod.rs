@@ -9,6 +9,7 @@ use std::clone::Clone;
 pub struct VecState<T> {
     index : usize,
     buffer: Vec<T>,
+    indents: Vec<usize>,
 }
 
 impl<A> FromIterator<A> for VecState<A> {
@@ -16,6 +17,7 @@ impl<A> FromIterator<A> for VecState<A> {
         VecState{
             index:0,
             buffer:Vec::from_iter(iterator.into_iter()),
+            indents:Vec::new(),
         }
     }
 }
@@ -25,14 +27,19 @@ pub trait State<T> {
     fn seek_to(&mut self, usize)->bool;
     fn next(&mut self)->Option<T>;
     fn next_by(&mut self, &Fn(&T)->bool)->Status<T>;
+    // Stack of reference positions for indentation sensitive parsers, None if the state don't keep it.
+    fn indents(&mut self)->Option<&mut Vec<usize>> {
+        None
+    }
 }
 
 impl<T> State<T> for VecState<T> where T:Clone {
     fn pos(&self) -> usize {
         self.index
     }
+    // The end of buffer is a valid position, so a parser can rewind to eof.
     fn seek_to(&mut self, to:usize) -> bool {
-        if 0 as usize <= to && to < self.buffer.len() {
+        if 0 as usize <= to && to <= self.buffer.len() {
             self.index = to;
             true
         } else {
@@ -61,6 +68,9 @@ impl<T> State<T> for VecState<T> where T:Clone {
             Err(SimpleError::new(self.index, String::from("eof")))
         }
     }
+    fn indents(&mut self)->Option<&mut Vec<usize>> {
+        Some(&mut self.indents)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -94,13 +104,13 @@ impl Error for SimpleError {
 }
 
 //pub trait Parsec<T:'static+Clone, R:'static+Clone>:Debug where Self:Parsec<T, R>+Clone+'static {
-pub trait Parsec<T, R>:Debug {
+pub trait Parsec<T, R>:Debug+Send+Sync {
     fn parse(&self, &mut State<T>)->Status<R>;
 }
 // TODO: move Generic Type Param P to bind/then/over function
 // Type Continuation(Result) Then Pass
 pub trait M<T:'static, R:'static>:Parsec<T, R> where Self:Clone+'static, T:Clone, R:Clone {
-    fn bind<P:'static+Clone>(self, binder:Arc<Box<Fn(&mut State<T>, R)->Status<P>>>)->Monad<T, R, P> {
+    fn bind<P:'static+Clone>(self, binder:Arc<Box<Fn(&mut State<T>, R)->Status<P>+Send+Sync>>)->Monad<T, R, P> {
         Monad::new(Arc::new(self), binder.clone())
     }
     fn then<P:'static+Clone>(self, then:Arc<Parsec<T, P>>)->Monad<T, R, P> {
@@ -129,12 +139,12 @@ pub type Status<T> = Result<T, SimpleError>;
 // Type Continuation Then Pass
 pub struct Monad<T, C, P> {
     parsec: Arc<Parsec<T, C>>,
-    binder: Arc<Box<Fn(&mut State<T>, C)->Status<P>>>,
+    binder: Arc<Box<Fn(&mut State<T>, C)->Status<P>+Send+Sync>>,
 }
 
 impl<T:'static, C:'static, P:'static> Monad<T, C, P>
 where T:Clone, P:Clone {
-    pub fn new(parsec: Arc<Parsec<T, C>>, binder: Arc<Box<Fn(&mut State<T>, C)->Status<P>>>)-> Monad<T, C, P> {
+    pub fn new(parsec: Arc<Parsec<T, C>>, binder: Arc<Box<Fn(&mut State<T>, C)->Status<P>+Send+Sync>>)-> Monad<T, C, P> {
         Monad{parsec:parsec.clone(), binder:binder.clone()}
     }
 }
@@ -262,12 +272,12 @@ pub fn parser<T:'static, R:'static>(parsec:Arc<Parsec<T, R>>)->Parser<T, R> wher
 
 // A monad just return bind
 pub struct Bind<T, R> {
-    binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>>>,
+    binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>+Send+Sync>>,
 }
 
 impl<T:'static, R:'static> Bind<T, R>
 where T:Clone, R:Clone {
-    pub fn new(binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>>>)-> Bind<T, R> {
+    pub fn new(binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>+Send+Sync>>)-> Bind<T, R> {
         Bind{binder:binder.clone()}
     }
 }
@@ -318,7 +328,7 @@ impl<T, R> Debug for Bind<T, R> where T:Clone, R:Clone{
 
 impl<T:'static, R:'static> M<T, R> for Bind<T, R> where T:Clone, R:Clone {}
 
-pub fn bind<T:'static, R:'static>(binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>>>)->Bind<T, R>
+pub fn bind<T:'static, R:'static>(binder: Arc<Box<Fn(&mut State<T>, T)->Status<R>+Send+Sync>>)->Bind<T, R>
 where T:Clone, R:Clone {
     Bind::new(binder)
 }
@@ -336,3 +346,18 @@ macro_rules! arc {
 pub mod atom;
 pub mod combinator;
 pub mod text;
+pub mod lexer;
+pub mod charclass;
+mod unicode;
+pub mod regex;
+pub mod indent;
+pub mod lexeme;
+pub mod grammar;
+pub mod lazy;
+pub mod peg;
+pub mod cst;
+pub mod incremental;
+pub mod parallel;
+pub mod iter;
+pub mod search;
+pub mod perm;
@@ -0,0 +1,104 @@
+use parsec::{State, VecState, SimpleError, Error, Parsec, Status};
+use std::cmp::max;
+use std::iter::FromIterator;
+use std::sync::Arc;
+use std::thread;
+
+// Parsecs are Send and Sync, so a grammar built once can be shared by Arc between threads.
+// parse_chunks_parallel splits the input after a delimiter, "\n" for lines or "\n\n" for blank
+// line separated records, so no record crosses two chunks. Every chunk is parsed record after
+// record on its own thread by a state counts positions from the start of whole input, so
+// errors and positions in results are global.
+
+// VecState of a chunk, positions are offsets in the whole input.
+struct ChunkState {
+    inner: VecState<char>,
+    offset: usize,
+}
+
+impl State<char> for ChunkState {
+    fn pos(&self) -> usize {
+        self.inner.pos() + self.offset
+    }
+    fn seek_to(&mut self, to:usize) -> bool {
+        to >= self.offset && self.inner.seek_to(to - self.offset)
+    }
+    fn next(&mut self) -> Option<char> {
+        self.inner.next()
+    }
+    fn next_by(&mut self, pred:&Fn(&char)->bool) -> Status<char> {
+        let pos = self.pos();
+        self.inner.next_by(pred).map_err(|err:SimpleError| SimpleError::new(pos, String::from(err.message())))
+    }
+    fn indents(&mut self) -> Option<&mut Vec<usize>> {
+        self.inner.indents()
+    }
+}
+
+// Offset and text of chunks in about the same size, every chunk but the last ends with the
+// delimiter.
+pub fn split_chunks(text:&str, delimiter:&str, count:usize) -> Vec<(usize, String)> {
+    let chars:Vec<char> = text.chars().collect();
+    let delimiter:Vec<char> = delimiter.chars().collect();
+    let size = max(chars.len() / max(count, 1), 1);
+    let mut re = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = chars.len();
+        if re.len() + 1 < count && !delimiter.is_empty() {
+            let mut i = max(start + size, start + 1) - 1;
+            while i + delimiter.len() <= chars.len() {
+                if chars[i..i + delimiter.len()] == delimiter[..] {
+                    end = i + delimiter.len();
+                    break;
+                }
+                i += 1;
+            }
+        }
+        re.push((start, chars[start..end].iter().cloned().collect()));
+        start = end;
+    }
+    re
+}
+
+fn parse_records<R>(p:&Parsec<char, R>, text:&str, offset:usize) -> Status<Vec<R>> {
+    let end = offset + text.chars().count();
+    let mut state = ChunkState{inner:VecState::from_iter(text.chars()), offset:offset};
+    let mut re = Vec::new();
+    while state.pos() < end {
+        let pos = state.pos();
+        match p.parse(&mut state) {
+            Ok(_) if state.pos() == pos => {
+                let message = format!("record parsec consume nothing at {}", pos);
+                return Err(SimpleError::new(pos, message));
+            }
+            Ok(x) => re.push(x),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(re)
+}
+
+// Parse records of text by p on a thread for each chunk. Results are in order of input, or
+// the first error of every chunk failed.
+pub fn parse_chunks_parallel<R:'static+Send>(p:Arc<Parsec<char, R>>, text:&str, delimiter:&str, threads:usize)
+        -> Result<Vec<R>, Vec<SimpleError>> {
+    let workers:Vec<(usize, thread::JoinHandle<Status<Vec<R>>>)> = split_chunks(text, delimiter, threads)
+        .into_iter().map(|(offset, chunk):(usize, String)| {
+            let p = p.clone();
+            (offset, thread::spawn(move || parse_records(&*p, chunk.as_str(), offset)))
+        }).collect();
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (offset, worker) in workers.into_iter() {
+        match worker.join() {
+            Ok(Ok(records)) => values.extend(records.into_iter()),
+            Ok(Err(err)) => errors.push(err),
+            Err(_) => {
+                let message = format!("parsec panicked in chunk start at {}", offset);
+                errors.push(SimpleError::new(offset, message));
+            }
+        }
+    }
+    if errors.is_empty() { Ok(values) } else { Err(errors) }
+}
@@ -0,0 +1,547 @@
+use parsec::{State, VecState, SimpleError, Error, Parsec, Status, Monad, M, parser};
+use parsec::atom::{pack, one, eq, eof, string, satisfy, one_of_str};
+use parsec::combinator::{either, try, many, many1, sep_by1, optional, look_ahead, not_followed_by};
+use parsec::charclass::{CharClass, range};
+use parsec::lexeme::Lexeme;
+use parsec::lazy::{ParserRef, lazy};
+use parsec::text::string_literal;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+// Grammar in PEG notation read at runtime, for grammars written out of rust code.
+//
+//     # comments start with #
+//     sum    <- number (('+' / '-') number)*
+//     number <- [0-9]+ / '(' sum ')'
+//
+// A rule is `name <- expression`, `=` and `::=` work as the arrow and a rule may end with ';'.
+// Expressions have 'literal', "literal", [a-z_] and [^"] classes, . for any char, (...) groups,
+// postfix * + ?, prefix & and ! lookahead, and choice by / or |. Choices are ordered as PEG, even
+// if written by |. The first rule is the start rule. Parsing gives a ParseTree for every rule
+// matched, literals and classes leave no node. Left recursive rules are rejected.
+
+// Expression of a rule, positions are char offsets in the grammar text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Rule(String, usize),
+    Literal(String),
+    Class(CharClass),
+    Any,
+    Sequence(Vec<Expr>),
+    Choice(Vec<(usize, Expr)>),
+    Many(Box<Expr>),
+    Many1(Box<Expr>),
+    Optional(Box<Expr>),
+    And(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub pos: usize,
+    pub expr: Expr,
+}
+
+// Rule matched from start to end of input, with trees of the rules matched inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTree {
+    pub rule: String,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<ParseTree>,
+}
+
+impl ParseTree {
+    // Text matched by the tree, source is the input it parsed from.
+    pub fn text(&self, source:&str) -> String {
+        source.chars().skip(self.start).take(self.end - self.start).collect()
+    }
+}
+
+impl Display for ParseTree {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        try!(write!(formatter, "{}[{}, {}]", self.rule, self.start, self.end));
+        if self.children.is_empty() {
+            return Ok(());
+        }
+        let children = self.children.iter().map(|c:&ParseTree| format!("{}", c)).collect::<Vec<String>>();
+        write!(formatter, "({})", children.join(" "))
+    }
+}
+
+fn lex() -> Lexeme {
+    Lexeme::new().line_comment("#")
+}
+
+fn position() -> Monad<char, (), usize> {
+    pack(()).bind(bnd!(|state:&mut State<char>, _:()|->Status<usize> { Ok(state.pos()) }))
+}
+
+fn identifier() -> Monad<char, char, String> {
+    let head = satisfy(bnd!(|c:&char| c.is_alphabetic() || *c == '_'), String::from("rule name"));
+    head.bind(bnd!(|state:&mut State<char>, c:char|->Status<String> {
+        let rest = satisfy(bnd!(|c:&char| c.is_alphanumeric() || *c == '_'), String::from("rule name"));
+        many(arc!(rest)).parse(state).map(|cs:Vec<char>| Some(c).into_iter().chain(cs.into_iter()).collect())
+    }))
+}
+
+fn arrow() -> Arc<Parsec<char, String>> {
+    let lex = lex();
+    let arrow = either(arc!(try(arc!(lex.symbol("<-")))), arc!(try(arc!(lex.symbol("::=")))));
+    arc!(either(arc!(arrow), arc!(lex.symbol("="))))
+}
+
+fn class_char(state:&mut State<char>, start:usize) -> Status<char> {
+    match state.next() {
+        Some('\\') => match state.next() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some(c) => Ok(c),
+            None => {
+                let message = format!("unterminated char class start at {}", start);
+                Err(SimpleError::new(start, message))
+            }
+        },
+        Some(c) => Ok(c),
+        None => {
+            let message = format!("unterminated char class start at {}", start);
+            Err(SimpleError::new(start, message))
+        }
+    }
+}
+
+// Body of [...] after the open bracket, ^ at first negates it and a-z is a range.
+fn class_body(state:&mut State<char>, start:usize) -> Status<CharClass> {
+    let pos = state.pos();
+    let negate = state.next() == Some('^');
+    if !negate {
+        state.seek_to(pos);
+    }
+    let mut singles = Vec::new();
+    let mut classes = Vec::new();
+    loop {
+        let pos = state.pos();
+        if state.next() == Some(']') {
+            break;
+        }
+        state.seek_to(pos);
+        let from = match class_char(state, start) {
+            Ok(c) => c,
+            Err(err) => return Err(err),
+        };
+        let pos = state.pos();
+        let dash = state.next() == Some('-');
+        let close = state.next() == Some(']');
+        state.seek_to(pos);
+        if dash && !close {
+            state.next();
+            match class_char(state, start) {
+                Ok(to) if from <= to => classes.push(range(from, to)),
+                Ok(to) => {
+                    let message = format!("invalid char range {:?}-{:?} at {}", from, to, pos - 1);
+                    return Err(SimpleError::new(pos - 1, message));
+                }
+                Err(err) => return Err(err),
+            }
+        } else {
+            singles.push(from);
+        }
+    }
+    if !singles.is_empty() {
+        classes.insert(0, CharClass::Chars(singles));
+    }
+    let class = match classes.len() {
+        0 => {
+            let message = format!("empty char class at {}", start);
+            return Err(SimpleError::new(start, message));
+        }
+        1 => classes.pop().unwrap(),
+        _ => CharClass::Union(classes),
+    };
+    Ok(if negate { class.negate() } else { class })
+}
+
+fn primary() -> Arc<Parsec<char, Expr>> {
+    let lex = lex();
+    // A name followed by an arrow starts the next rule.
+    let name = parser(arc!(lex.lexeme(arc!(identifier())))).over(arc!(not_followed_by(arrow())));
+    let reference = position().bind(bnd!(move |state:&mut State<char>, pos:usize|->Status<Expr> {
+        name.parse(state).map(|name:String| Expr::Rule(name, pos))
+    }));
+    let group = lex.symbol("(").then(arc!(lazy(expression))).over(arc!(lex.symbol(")")));
+    let quoted = look_ahead(arc!(one_of_str("\"'"))).bind(bnd!(|state:&mut State<char>, quote:char|->Status<String> {
+        string_literal(quote).parse(state)
+    }));
+    let literal = lex.lexeme(arc!(quoted)).bind(bnd!(|_:&mut State<char>, text:String|->Status<Expr> {
+        Ok(Expr::Literal(text))
+    }));
+    let class = lex.lexeme(arc!(eq('[').bind(bnd!(|state:&mut State<char>, _:char|->Status<CharClass> {
+        let start = state.pos() - 1;
+        class_body(state, start)
+    })))).bind(bnd!(|_:&mut State<char>, class:CharClass|->Status<Expr> { Ok(Expr::Class(class)) }));
+    let any = lex.symbol(".").then(arc!(pack(Expr::Any)));
+    // Only the reference backtracks, others fail without consume or after their first char.
+    let alts = either(arc!(try(arc!(reference))), arc!(group))
+        .or(arc!(literal))
+        .or(arc!(class))
+        .or(arc!(any));
+    arc!(alts)
+}
+
+fn prefix() -> Arc<Parsec<char, Expr>> {
+    let lex = lex();
+    let postfix = optional(arc!(lex.lexeme(arc!(one_of_str("*+?")))));
+    let primary = primary();
+    let suffix = parser(primary).bind(bnd!(move |state:&mut State<char>, e:Expr|->Status<Expr> {
+        postfix.parse(state).map(|op:Option<char>| match op {
+            Some('*') => Expr::Many(Box::new(e)),
+            Some('+') => Expr::Many1(Box::new(e)),
+            Some(_) => Expr::Optional(Box::new(e)),
+            None => e,
+        })
+    }));
+    let lookahead = optional(arc!(lex.lexeme(arc!(one_of_str("&!")))));
+    arc!(lookahead.bind(bnd!(move |state:&mut State<char>, op:Option<char>|->Status<Expr> {
+        suffix.parse(state).map(|e:Expr| match op {
+            Some('&') => Expr::And(Box::new(e)),
+            Some(_) => Expr::Not(Box::new(e)),
+            None => e,
+        })
+    })))
+}
+
+fn expression() -> Arc<Parsec<char, Expr>> {
+    let lex = lex();
+    let item = prefix();
+    let sequence = pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Expr> {
+        let mut items = many(item.clone()).parse(state).unwrap();
+        // An item failed after consume some input is an error, not the end of sequence.
+        let pos = state.pos();
+        if let Err(err) = item.parse(state) {
+            if state.pos() != pos {
+                return Err(err);
+            }
+        }
+        state.seek_to(pos);
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Sequence(items) })
+    }));
+    let alternative = position().bind(bnd!(move |state:&mut State<char>, pos:usize|->Status<(usize, Expr)> {
+        sequence.parse(state).map(|e:Expr| (pos, e))
+    }));
+    let bar = either(arc!(try(arc!(lex.symbol("/")))), arc!(lex.symbol("|")));
+    arc!(sep_by1(arc!(bar), arc!(alternative)).bind(bnd!(|_:&mut State<char>, mut alts:Vec<(usize, Expr)>|->Status<Expr> {
+        Ok(if alts.len() == 1 { alts.pop().unwrap().1 } else { Expr::Choice(alts) })
+    })))
+}
+
+fn definition() -> Arc<Parsec<char, Definition>> {
+    let lex = lex();
+    let name = lex.lexeme(arc!(identifier()));
+    let arrow = arrow();
+    let expression = expression();
+    let end = optional(arc!(lex.symbol(";")));
+    arc!(position().bind(bnd!(move |state:&mut State<char>, pos:usize|->Status<Definition> {
+        let name = match name.parse(state) {
+            Ok(name) => name,
+            Err(err) => return Err(err),
+        };
+        let expr = match arrow.parse(state).and_then(|_| expression.parse(state)) {
+            Ok(expr) => expr,
+            Err(err) => return Err(err),
+        };
+        end.parse(state).map(|_| Definition{name:name, pos:pos, expr:expr})
+    })))
+}
+
+// Rules of grammar text, without checking them.
+pub fn read(text:&str) -> Status<Vec<Definition>> {
+    let mut state = VecState::from_iter(text.chars());
+    let definitions = try!(lex().whole(arc!(many1(definition()))).parse(&mut state));
+    if eof().parse(&mut state).is_ok() {
+        return Ok(definitions);
+    }
+    // The definition stopped many1 tells what is wrong.
+    let pos = state.pos();
+    match definition().parse(&mut state) {
+        Err(err) => Err(err),
+        Ok(_) => Err(SimpleError::new(pos, format!("unexpected input at {}", pos))),
+    }
+}
+
+// Facts of rules, repeat until no rule changes since rules may refer to each other.
+fn fixpoint(definitions:&Vec<Definition>, f:fn(&Expr, &HashMap<String, bool>)->bool) -> HashMap<String, bool> {
+    let mut facts:HashMap<String, bool> = definitions.iter()
+        .map(|d:&Definition| (d.name.clone(), false)).collect();
+    loop {
+        let mut changed = false;
+        for d in definitions.iter() {
+            if !facts.get(&d.name).cloned().unwrap_or(false) && f(&d.expr, &facts) {
+                facts.insert(d.name.clone(), true);
+                changed = true;
+            }
+        }
+        if !changed {
+            return facts;
+        }
+    }
+}
+
+// Expression may success without consume anything.
+fn nullable(e:&Expr, rules:&HashMap<String, bool>) -> bool {
+    match *e {
+        Expr::Rule(ref name, _) => rules.get(name).cloned().unwrap_or(false),
+        Expr::Literal(ref text) => text.is_empty(),
+        Expr::Class(_) | Expr::Any => false,
+        Expr::Sequence(ref items) => items.iter().all(|e:&Expr| nullable(e, rules)),
+        Expr::Choice(ref alts) => alts.iter().any(|&(_, ref e):&(usize, Expr)| nullable(e, rules)),
+        Expr::Many1(ref e) => nullable(e, rules),
+        Expr::Many(_) | Expr::Optional(_) | Expr::And(_) | Expr::Not(_) => true,
+    }
+}
+
+// Expression never fails, false when it is unknown.
+fn always(e:&Expr, rules:&HashMap<String, bool>) -> bool {
+    match *e {
+        Expr::Rule(ref name, _) => rules.get(name).cloned().unwrap_or(false),
+        Expr::Literal(ref text) => text.is_empty(),
+        Expr::Class(_) | Expr::Any | Expr::Not(_) => false,
+        Expr::Sequence(ref items) => items.iter().all(|e:&Expr| always(e, rules)),
+        Expr::Choice(ref alts) => alts.iter().any(|&(_, ref e):&(usize, Expr)| always(e, rules)),
+        Expr::Many1(ref e) | Expr::And(ref e) => always(e, rules),
+        Expr::Many(_) | Expr::Optional(_) => true,
+    }
+}
+
+// Rules expression may call before it consumes anything.
+fn left_calls(e:&Expr, rules:&HashMap<String, bool>, calls:&mut Vec<String>) {
+    match *e {
+        Expr::Rule(ref name, _) => calls.push(name.clone()),
+        Expr::Literal(_) | Expr::Class(_) | Expr::Any => (),
+        Expr::Sequence(ref items) => {
+            for item in items.iter() {
+                left_calls(item, rules, calls);
+                if !nullable(item, rules) {
+                    break;
+                }
+            }
+        }
+        Expr::Choice(ref alts) => {
+            for &(_, ref e) in alts.iter() {
+                left_calls(e, rules, calls);
+            }
+        }
+        Expr::Many(ref e) | Expr::Many1(ref e) | Expr::Optional(ref e) | Expr::And(ref e) | Expr::Not(ref e) => {
+            left_calls(e, rules, calls)
+        }
+    }
+}
+
+struct Checker {
+    names: HashSet<String>,
+    nullable: HashMap<String, bool>,
+    always: HashMap<String, bool>,
+}
+
+impl Checker {
+    fn check(&self, rule:&str, e:&Expr) -> Result<(), String> {
+        match *e {
+            Expr::Rule(ref name, pos) if !self.names.contains(name) => {
+                Err(format!("undefined rule {} at {}", name, pos))
+            }
+            Expr::Rule(_, _) | Expr::Literal(_) | Expr::Class(_) | Expr::Any => Ok(()),
+            Expr::Sequence(ref items) => {
+                for item in items.iter() {
+                    try!(self.check(rule, item));
+                }
+                Ok(())
+            }
+            Expr::Choice(ref alts) => {
+                for (i, &(pos, ref e)) in alts.iter().enumerate() {
+                    try!(self.check(rule, e));
+                    for &(before, ref prior) in alts[..i].iter() {
+                        if always(prior, &self.always) {
+                            return Err(format!("alternative at {} is unreachable, alternative at {} always matches",
+                                               pos, before));
+                        }
+                        if let (&Expr::Literal(ref prior), &Expr::Literal(ref text)) = (prior, e) {
+                            if text.starts_with(prior.as_str()) {
+                                return Err(format!("alternative at {} is unreachable, {:?} at {} matches its prefix",
+                                                   pos, prior, before));
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Expr::Many(ref item) | Expr::Many1(ref item) => {
+                if nullable(item, &self.nullable) {
+                    return Err(format!("repetition in rule {} may match empty input forever", rule));
+                }
+                self.check(rule, item)
+            }
+            Expr::Optional(ref item) | Expr::And(ref item) | Expr::Not(ref item) => self.check(rule, item),
+        }
+    }
+}
+
+// Find duplicate and undefined rules, unreachable alternatives, repetitions never end and left
+// recursion.
+pub fn check(definitions:&Vec<Definition>) -> Result<(), String> {
+    let mut defined:HashMap<String, usize> = HashMap::new();
+    for d in definitions.iter() {
+        if let Some(pos) = defined.get(&d.name) {
+            return Err(format!("rule {} at {} is already defined at {}", d.name, d.pos, pos));
+        }
+        defined.insert(d.name.clone(), d.pos);
+    }
+    let checker = Checker{
+        names: definitions.iter().map(|d:&Definition| d.name.clone()).collect(),
+        nullable: fixpoint(definitions, nullable),
+        always: fixpoint(definitions, always),
+    };
+    for d in definitions.iter() {
+        try!(checker.check(d.name.as_str(), &d.expr));
+    }
+    let exprs:HashMap<&str, &Expr> = definitions.iter().map(|d:&Definition| (d.name.as_str(), &d.expr)).collect();
+    for d in definitions.iter() {
+        // Rules reachable from d without consuming, d is left recursive if it is one of them.
+        let mut calls = Vec::new();
+        left_calls(&d.expr, &checker.nullable, &mut calls);
+        let mut seen:HashSet<String> = HashSet::new();
+        while let Some(name) = calls.pop() {
+            if name == d.name {
+                return Err(format!("left recursive rule {} at {}", d.name, d.pos));
+            }
+            if seen.insert(name.clone()) {
+                left_calls(exprs[name.as_str()], &checker.nullable, &mut calls);
+            }
+        }
+    }
+    Ok(())
+}
+
+type Trees = Arc<Parsec<char, Vec<ParseTree>>>;
+
+fn empty<R:'static+Clone>(p:Arc<Parsec<char, R>>) -> Trees {
+    arc!(parser(p).then(arc!(pack(Vec::new()))))
+}
+
+fn flatten(p:Arc<Parsec<char, Vec<Vec<ParseTree>>>>) -> Trees {
+    arc!(parser(p).bind(bnd!(|_:&mut State<char>, xs:Vec<Vec<ParseTree>>|->Status<Vec<ParseTree>> {
+        Ok(xs.into_iter().flat_map(|x:Vec<ParseTree>| x.into_iter()).collect())
+    })))
+}
+
+fn compile(e:&Expr, rules:&HashMap<String, ParserRef<char, Vec<ParseTree>>>) -> Trees {
+    match *e {
+        Expr::Rule(ref name, _) => arc!(rules.get(name).unwrap().clone()),
+        Expr::Literal(ref text) => empty(arc!(string(text.as_str()))),
+        Expr::Class(ref class) => empty(arc!(class.parser())),
+        Expr::Any => empty(arc!(one::<char>())),
+        Expr::Sequence(ref items) => {
+            let items:Vec<Trees> = items.iter().map(|e:&Expr| compile(e, rules)).collect();
+            arc!(pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Vec<ParseTree>> {
+                let mut re = Vec::new();
+                for p in items.iter() {
+                    match p.parse(state) {
+                        Ok(trees) => re.extend(trees.into_iter()),
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(re)
+            })))
+        }
+        Expr::Choice(ref alts) => {
+            // Every alternative backtracks, as the ordered choice of PEG.
+            let mut alts = alts.iter().map(|&(_, ref e):&(usize, Expr)| -> Trees { arc!(try(compile(e, rules))) });
+            let first = alts.next().unwrap();
+            alts.fold(first, |acc:Trees, p:Trees| arc!(either(acc, p)))
+        }
+        Expr::Many(ref e) => flatten(arc!(many(compile(e, rules)))),
+        Expr::Many1(ref e) => flatten(arc!(many1(arc!(try(compile(e, rules)))))),
+        Expr::Optional(ref e) => {
+            let p = optional(compile(e, rules));
+            arc!(p.bind(bnd!(|_:&mut State<char>, x:Option<Vec<ParseTree>>|->Status<Vec<ParseTree>> {
+                Ok(x.unwrap_or(Vec::new()))
+            })))
+        }
+        Expr::And(ref e) => empty(arc!(look_ahead(compile(e, rules)))),
+        Expr::Not(ref e) => empty(arc!(not_followed_by(compile(e, rules)))),
+    }
+}
+
+fn node(name:String, body:Trees) -> Trees {
+    arc!(pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Vec<ParseTree>> {
+        let start = state.pos();
+        match body.parse(state) {
+            Ok(children) => Ok(vec![ParseTree{rule:name.clone(), start:start, end:state.pos(), children:children}]),
+            Err(err) => Err(err),
+        }
+    })))
+}
+
+// Parsers of a checked grammar. Recursive rules refer to each other by ParserRef, that cycle
+// is never freed, so build a Peg once and reuse it.
+#[derive(Debug, Clone)]
+pub struct Peg {
+    start: String,
+    rules: HashMap<String, ParserRef<char, Vec<ParseTree>>>,
+}
+
+impl Peg {
+    pub fn new(grammar:&str) -> Result<Peg, String> {
+        let definitions = match read(grammar) {
+            Ok(definitions) => definitions,
+            Err(err) => return Err(format!("invalid grammar: {}", err.message())),
+        };
+        try!(check(&definitions));
+        let mut rules = HashMap::new();
+        for d in definitions.iter() {
+            rules.insert(d.name.clone(), ParserRef::new());
+        }
+        for d in definitions.iter() {
+            let body = compile(&d.expr, &rules);
+            rules.get(&d.name).unwrap().set(node(d.name.clone(), body));
+        }
+        Ok(Peg{start:definitions[0].name.clone(), rules:rules})
+    }
+
+    pub fn start(&self) -> &str {
+        self.start.as_str()
+    }
+
+    pub fn rule(&self, name:&str) -> Option<Arc<Parsec<char, ParseTree>>> {
+        self.rules.get(name).map(|r:&ParserRef<char, Vec<ParseTree>>| -> Arc<Parsec<char, ParseTree>> {
+            arc!(parser(arc!(r.clone())).bind(bnd!(|_:&mut State<char>, mut trees:Vec<ParseTree>|->Status<ParseTree> {
+                Ok(trees.pop().unwrap())
+            })))
+        })
+    }
+
+    // Parse whole text by the rule.
+    pub fn parse_rule(&self, name:&str, text:&str) -> Status<ParseTree> {
+        let p = match self.rule(name) {
+            Some(p) => p,
+            None => return Err(SimpleError::new(0, format!("undefined rule {}", name))),
+        };
+        let mut state = VecState::from_iter(text.chars());
+        let tree = try!(p.parse(&mut state));
+        let pos = state.pos();
+        match eof().parse(&mut state) {
+            Ok(_) => Ok(tree),
+            Err(_) => {
+                Err(SimpleError::new(pos, format!("expect end of input at {} after rule {}", pos, name)))
+            }
+        }
+    }
+
+    // Parse whole text by the start rule.
+    pub fn parse(&self, text:&str) -> Status<ParseTree> {
+        self.parse_rule(self.start.as_str(), text)
+    }
+}
@@ -0,0 +1,157 @@
+use parsec::{State, Parsec, Status, Monad, M, parser};
+use parsec::atom::pack;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// Permutation phrases as Text.Parsec.Perm. The items are parsed in any order, each at most
+// once, and their results are given in declared order. An optional item not found takes its
+// default, a required item not found fails the parse by its own error at the position after
+// the items found. An item matched once is not tried again, so a duplicate is left to the
+// parsec after the permutation.
+pub struct PermItem<T, R> {
+    parsec: Arc<Parsec<T, R>>,
+    default: Option<R>,
+}
+
+impl<T, R> PermItem<T, R> where R:Clone {
+    pub fn new(p:Arc<Parsec<T, R>>, default:Option<R>) -> PermItem<T, R> {
+        PermItem{parsec:p, default:default}
+    }
+}
+
+impl<T, R> Clone for PermItem<T, R> where R:Clone {
+    fn clone(&self)->Self {
+        PermItem{parsec:self.parsec.clone(), default:self.default.clone()}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.parsec = source.parsec.clone();
+        self.default = source.default.clone();
+    }
+}
+
+// An item must appear.
+pub fn perm_item<T, R:Clone>(p:Arc<Parsec<T, R>>) -> PermItem<T, R> {
+    PermItem::new(p, None)
+}
+
+// An item may be missing, then its result is default.
+pub fn perm_default<T, R:Clone>(p:Arc<Parsec<T, R>>, default:R) -> PermItem<T, R> {
+    PermItem::new(p, Some(default))
+}
+
+// An item may be missing, then its result is None.
+pub fn perm_option<T:'static+Clone, R:'static+Clone+Send+Sync>(p:Arc<Parsec<T, R>>) -> PermItem<T, Option<R>> {
+    let some = parser(p).bind(bnd!(|_:&mut State<T>, x:R|->Status<Option<R>> { Ok(Some(x)) }));
+    PermItem::new(arc!(some), Some(None))
+}
+
+// Parse the items left in any order until none of them matches, run(i, state) parses item i
+// and keeps its result.
+fn permute<T>(state:&mut State<T>, count:usize, run:&mut FnMut(usize, &mut State<T>)->bool) {
+    let mut done:Vec<bool> = (0..count).map(|_| false).collect();
+    loop {
+        let mut progress = false;
+        for i in 0..count {
+            if done[i] {
+                continue;
+            }
+            let pos = state.pos();
+            if run(i, state) {
+                done[i] = true;
+                progress = true;
+                break;
+            }
+            state.seek_to(pos);
+        }
+        if !progress {
+            return;
+        }
+    }
+}
+
+fn keep<T, R>(item:&PermItem<T, R>, state:&mut State<T>, slot:&mut Option<R>) -> bool {
+    match item.parsec.parse(state) {
+        Ok(x) => {
+            *slot = Some(x);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// Result of an item after the permutation. A missing required item failed at current
+// position, run it again for the error.
+fn finish<T, R:Clone>(item:&PermItem<T, R>, slot:Option<R>, state:&mut State<T>) -> Status<R> {
+    match slot {
+        Some(x) => Ok(x),
+        None => match item.default {
+            Some(ref x) => Ok(x.clone()),
+            None => item.parsec.parse(state),
+        },
+    }
+}
+
+// Items of the same type, results in declared order.
+pub fn permutation<T:'static, R:'static>(items:Vec<PermItem<T, R>>) -> Monad<T, (), Vec<R>>
+where T:Clone, R:Clone+Debug+Send+Sync {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<Vec<R>> {
+        let mut slots:Vec<Option<R>> = items.iter().map(|_| None).collect();
+        permute(state, items.len(), &mut |i:usize, state:&mut State<T>| keep(&items[i], state, &mut slots[i]));
+        let mut re = Vec::new();
+        for (item, slot) in items.iter().zip(slots.into_iter()) {
+            re.push(try!(finish(item, slot, state)));
+        }
+        Ok(re)
+    }))
+}
+
+pub fn perm2<T:'static, A:'static, B:'static>(a:PermItem<T, A>, b:PermItem<T, B>) -> Monad<T, (), (A, B)>
+where T:Clone, A:Clone+Debug+Send+Sync, B:Clone+Debug+Send+Sync {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<(A, B)> {
+        let (mut x, mut y) = (None, None);
+        permute(state, 2, &mut |i:usize, state:&mut State<T>| match i {
+            0 => keep(&a, state, &mut x),
+            _ => keep(&b, state, &mut y),
+        });
+        let x = try!(finish(&a, x, state));
+        let y = try!(finish(&b, y, state));
+        Ok((x, y))
+    }))
+}
+
+pub fn perm3<T:'static, A:'static, B:'static, C:'static>(a:PermItem<T, A>, b:PermItem<T, B>, c:PermItem<T, C>)
+        -> Monad<T, (), (A, B, C)>
+where T:Clone, A:Clone+Debug+Send+Sync, B:Clone+Debug+Send+Sync, C:Clone+Debug+Send+Sync {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<(A, B, C)> {
+        let (mut x, mut y, mut z) = (None, None, None);
+        permute(state, 3, &mut |i:usize, state:&mut State<T>| match i {
+            0 => keep(&a, state, &mut x),
+            1 => keep(&b, state, &mut y),
+            _ => keep(&c, state, &mut z),
+        });
+        let x = try!(finish(&a, x, state));
+        let y = try!(finish(&b, y, state));
+        let z = try!(finish(&c, z, state));
+        Ok((x, y, z))
+    }))
+}
+
+pub fn perm4<T:'static, A:'static, B:'static, C:'static, D:'static>
+        (a:PermItem<T, A>, b:PermItem<T, B>, c:PermItem<T, C>, d:PermItem<T, D>) -> Monad<T, (), (A, B, C, D)>
+where T:Clone, A:Clone+Debug+Send+Sync, B:Clone+Debug+Send+Sync, C:Clone+Debug+Send+Sync, D:Clone+Debug+Send+Sync {
+    pack(()).bind(bnd!(move |state:&mut State<T>, _:()|->Status<(A, B, C, D)> {
+        let (mut x, mut y, mut z, mut w) = (None, None, None, None);
+        permute(state, 4, &mut |i:usize, state:&mut State<T>| match i {
+            0 => keep(&a, state, &mut x),
+            1 => keep(&b, state, &mut y),
+            2 => keep(&c, state, &mut z),
+            _ => keep(&d, state, &mut w),
+        });
+        let x = try!(finish(&a, x, state));
+        let y = try!(finish(&b, y, state));
+        let z = try!(finish(&c, z, state));
+        let w = try!(finish(&d, w, state));
+        Ok((x, y, z, w))
+    }))
+}
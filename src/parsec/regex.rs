@@ -0,0 +1,140 @@
+use parsec::{State, SimpleError, Parsec, Status, M};
+use regex::Regex;
+use std::fmt::{Debug, Formatter};
+use std::cmp::max;
+use std::fmt;
+use std::sync::Arc;
+
+// Result of regex atom, groups[0] is the whole match as regex crate does.
+// start and end are positions of state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexMatch {
+    pub text: String,
+    pub groups: Vec<Option<String>>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl RegexMatch {
+    pub fn group(&self, index:usize) -> Option<&str> {
+        self.groups.get(index).and_then(|g:&Option<String>| g.as_ref().map(|s:&String| s.as_str()))
+    }
+}
+
+// Regex match at current position only. The regex sees a window of the next chars, 1024 by
+// default, so a parse costs the window instead of the rest of input. A match reaching the end
+// of window is tried again in a doubled window, so long runs as [a-z]+ are matched whole. But
+// a regex fails, or choose a shorter alternative, if it needs chars after the window to decide,
+// so set the window longer than the tokens it matches.
+pub struct RegexParsec {
+    source: Arc<String>,
+    regex: Arc<Regex>,
+    window: usize,
+}
+
+impl RegexParsec {
+    pub fn new(pattern:&str) -> Result<RegexParsec, String> {
+        let anchored = format!("^(?:{})", pattern);
+        match Regex::new(anchored.as_str()) {
+            Ok(re) => Ok(RegexParsec{source:Arc::new(String::from(pattern)), regex:Arc::new(re), window:1024}),
+            Err(err) => Err(format!("invalid regex /{}/: {}", pattern, err)),
+        }
+    }
+
+    // Count of chars the regex sees at first, at least one.
+    pub fn window(mut self, size:usize) -> RegexParsec {
+        self.window = max(size, 1);
+        self
+    }
+}
+
+// Read chars after the text until it has size chars, false if the state ends before.
+fn fill(state:&mut State<char>, text:&mut String, count:&mut usize, size:usize) -> bool {
+    while *count < size {
+        match state.next() {
+            Some(c) => {
+                text.push(c);
+                *count += 1;
+            }
+            None => return false,
+        }
+    }
+    true
+}
+
+impl Parsec<char, RegexMatch> for RegexParsec {
+    fn parse(&self, state:&mut State<char>)->Status<RegexMatch> {
+        let start = state.pos();
+        let mut text = String::new();
+        let mut count = 0;
+        let mut size = self.window;
+        loop {
+            let more = fill(state, &mut text, &mut count, size);
+            let groups:Vec<Option<String>> = match self.regex.captures(text.as_str()) {
+                Some(caps) => (0..caps.len()).map(|idx:usize| caps.at(idx).map(|g:&str| String::from(g))).collect(),
+                None => {
+                    state.seek_to(start);
+                    let message = format!("expect /{}/ at {} but mismatch", self.source, start);
+                    return Err(SimpleError::new(start, message));
+                }
+            };
+            let whole = groups[0].clone().unwrap_or(String::new());
+            let length = whole.chars().count();
+            // The match may go on after the window.
+            if more && length == count {
+                size *= 2;
+                continue;
+            }
+            state.seek_to(start + length);
+            return Ok(RegexMatch{text:whole, groups:groups, start:start, end:start + length});
+        }
+    }
+}
+
+impl<'a> FnOnce<(&'a mut State<char>, )> for RegexParsec {
+    type Output = Status<RegexMatch>;
+    extern "rust-call" fn call_once(self, _: (&'a mut State<char>, )) -> Status<RegexMatch> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> FnMut<(&'a mut State<char>, )> for RegexParsec {
+    extern "rust-call" fn call_mut(&mut self, _: (&'a mut State<char>, )) -> Status<RegexMatch> {
+        panic!("Not implement!");
+    }
+}
+
+impl<'a> Fn<(&'a mut State<char>, )> for RegexParsec {
+    extern "rust-call" fn call(&self, args: (&'a mut State<char>, )) -> Status<RegexMatch> {
+        let (state, ) = args;
+        self.parse(state)
+    }
+}
+
+impl Clone for RegexParsec {
+    fn clone(&self)->Self {
+        RegexParsec{source:self.source.clone(), regex:self.regex.clone(), window:self.window}
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.source = source.source.clone();
+        self.regex = source.regex.clone();
+        self.window = source.window;
+    }
+}
+
+impl Debug for RegexParsec {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "<regex parsec /{}/>", self.source)
+    }
+}
+
+impl M<char, RegexMatch> for RegexParsec{}
+
+// Panic if the pattern is invalid, use RegexParsec::new to check it.
+pub fn regex(pattern:&str) -> RegexParsec {
+    match RegexParsec::new(pattern) {
+        Ok(p) => p,
+        Err(message) => panic!("{}", message),
+    }
+}
@@ -0,0 +1,69 @@
+use parsec::{State, VecState, Parsec};
+use parsec::cst::Spanned;
+use std::iter::FromIterator;
+
+// Search mode, find matches of a parsec anywhere in the input. The parsec is tried at every
+// position, when it fails the search skip one item and try again. An empty match is kept, then
+// the search moves one item after it, so it always reaches the end.
+
+// The first match from current position, state is after the match, or at the end if not found.
+pub fn find_first<T, R>(p:&Parsec<T, R>, state:&mut State<T>) -> Option<Spanned<R>> {
+    loop {
+        let start = state.pos();
+        if let Ok(value) = p.parse(state) {
+            return Some(Spanned{value:value, start:start, end:state.pos()});
+        }
+        state.seek_to(start);
+        if state.next().is_none() {
+            return None;
+        }
+    }
+}
+
+// All matches do not overlap from current position to the end.
+pub fn find_all<T, R>(p:&Parsec<T, R>, state:&mut State<T>) -> Vec<Spanned<R>> {
+    let mut re = Vec::new();
+    while let Some(found) = find_first(p, state) {
+        let empty = found.start == found.end;
+        re.push(found);
+        if empty && state.next().is_none() {
+            break;
+        }
+    }
+    re
+}
+
+fn text_of(chars:&[char]) -> String {
+    chars.iter().cloned().collect()
+}
+
+// Pieces of text between matches of separator p, empty matches don't split.
+pub fn split_by<R>(p:&Parsec<char, R>, text:&str) -> Vec<String> {
+    let chars:Vec<char> = text.chars().collect();
+    let mut state = VecState::from_iter(text.chars());
+    let mut re = Vec::new();
+    let mut last = 0;
+    for found in find_all(p, &mut state).into_iter() {
+        if found.start < found.end {
+            re.push(text_of(&chars[last..found.start]));
+            last = found.end;
+        }
+    }
+    re.push(text_of(&chars[last..]));
+    re
+}
+
+// Text with every match of p replaced by f of the match.
+pub fn replace_all<R, F>(p:&Parsec<char, R>, text:&str, f:F) -> String where F:Fn(&Spanned<R>)->String {
+    let chars:Vec<char> = text.chars().collect();
+    let mut state = VecState::from_iter(text.chars());
+    let mut re = String::new();
+    let mut last = 0;
+    for found in find_all(p, &mut state).iter() {
+        re.push_str(text_of(&chars[last..found.start]).as_str());
+        re.push_str(f(found).as_str());
+        last = found.end;
+    }
+    re.push_str(text_of(&chars[last..]).as_str());
+    re
+}
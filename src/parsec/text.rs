@@ -1,22 +1,20 @@
-use parsec::{State, SimpleError, Status, Parsec, Monad, M, Bind, parser, bind};
-use parsec::combinator::{Either, either, try, many1};
-use parsec::atom::{OneOf, pack, eq, one_of};
+use parsec::{State, SimpleError, Status, Parsec, Monad, M, parser};
+use parsec::combinator::{Either, Skip, Skip1, either, try, many1, skip_many, skip_many1, look_ahead};
+use parsec::atom::{OneOf, Satisfy, pack, eq, ne, one_of, satisfy, string, peek, expect, scan};
+use parsec::charclass::{Category, category};
 use std::sync::Arc;
 use std::boxed::Box;
+use std::char;
+use std::{i32, i64, f64};
+#[cfg(feature = "bigint")]
+use num::bigint::BigInt;
 
 pub fn space() -> OneOf<char> {
     one_of(&vec![' ', '\t'])
 }
 
-pub fn white_space() -> Bind<char, char> {
-    bind(bnd!(|state: &mut State<char>, x:char|->Status<char> {
-        if x.is_whitespace() {
-            Ok(x)
-        } else {
-            let message = format!("Expect space but got {}", x);
-            Err(SimpleError::new(state.pos(), String::from(message)))
-        }
-    }))
+pub fn white_space() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_whitespace()), String::from("white space"))
 }
 
 pub fn newline() -> Either<char, String> {
@@ -27,48 +25,40 @@ pub fn newline() -> Either<char, String> {
     either(arc!(rel.then(thn.clone())), arc!(nl.then(arc!(pack(String::from("\n"))))))
 }
 
-pub fn digit() -> Bind<char, char> {
-    bind(bnd!(|state: &mut State<char>, x:char|->Status<char> {
-        if x.is_numeric() {
-            Ok(x)
-        } else {
-            let message = format!("Expect numeric but got {}", x);
-            Err(SimpleError::new(state.pos(), String::from(message)))
-        }
-    }))
+pub fn digit() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_numeric()), String::from("numeric"))
 }
 
-pub fn alpha() -> Bind<char, char> {
-    bind(bnd!(|state: &mut State<char>, x:char|->Status<char> {
-        if x.is_alphabetic() {
-            Ok(x)
-        } else {
-            let message = format!("Expect alphabetic but got {}", x);
-            Err(SimpleError::new(state.pos(), String::from(message)))
-        }
-    }))
+pub fn alpha() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_alphabetic()), String::from("alphabetic"))
 }
 
-pub fn alphanumeric() -> Bind<char, char> {
-    bind(bnd!(|state: &mut State<char>, x:char|->Status<char> {
-        if x.is_alphanumeric() {
-            Ok(x)
-        } else {
-            let message = format!("Expect alphabetic or number but got {}", x);
-            Err(SimpleError::new(state.pos(), String::from(message)))
-        }
-    }))
+pub fn alphanumeric() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_alphanumeric()), String::from("alphabetic or number"))
 }
 
-pub fn control() -> Bind<char, char> {
-    bind(bnd!(|state: &mut State<char>, x:char|->Status<char> {
-        if x.is_control() {
-            Ok(x)
-        } else {
-            let message = format!("Expect control but got {}", x);
-            Err(SimpleError::new(state.pos(), String::from(message)))
-        }
-    }))
+pub fn control() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_control()), String::from("control"))
+}
+
+pub fn hex_digit() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_digit(16)), String::from("hex digit"))
+}
+
+pub fn oct_digit() -> Satisfy<char> {
+    satisfy(bnd!(|x:&char| x.is_digit(8)), String::from("octal digit"))
+}
+
+pub fn upper() -> Satisfy<char> {
+    category(Category::Lu).parser()
+}
+
+pub fn lower() -> Satisfy<char> {
+    category(Category::Ll).parser()
+}
+
+pub fn punctuation() -> Satisfy<char> {
+    category(Category::P).parser()
 }
 
 pub fn uinteger() -> Monad<char, Vec<char>, String> {
@@ -111,3 +101,425 @@ pub fn float() ->Either<char, String>{
         })
     }))), arc!(ufloat()))
 }
+
+// Parse the escape sequence after a backslash, errors point to the backslash at start.
+fn escape(state: &mut State<char>, start:usize, quote:char) -> Status<char> {
+    let next = state.next();
+    if next.is_none() {
+        let message = format!("unterminated escape sequence at {}", start);
+        return Err(SimpleError::new(start, message));
+    }
+    let x = next.unwrap();
+    match x {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '\\' => Ok('\\'),
+        '"' => Ok('"'),
+        '\'' => Ok('\''),
+        'x' => {
+            let mut code = 0;
+            for _ in 0..2 {
+                match state.next().and_then(|c:char| c.to_digit(16)) {
+                    Some(d) => code = code * 16 + d,
+                    None => {
+                        let message = format!("invalid escape \\x at {}, expect two hex digits", start);
+                        return Err(SimpleError::new(start, message));
+                    }
+                }
+            }
+            Ok(char::from_u32(code).unwrap())
+        }
+        'u' => {
+            if state.next() != Some('{') {
+                let message = format!("invalid escape \\u at {}, expect {{", start);
+                return Err(SimpleError::new(start, message));
+            }
+            let mut code:u32 = 0;
+            let mut count = 0;
+            loop {
+                match state.next() {
+                    Some('}') if count > 0 => break,
+                    Some(c) if c.is_digit(16) && count < 6 => {
+                        code = code * 16 + c.to_digit(16).unwrap();
+                        count += 1;
+                    }
+                    _ => {
+                        let message = format!("invalid escape \\u at {}, expect 1 to 6 hex digits in braces", start);
+                        return Err(SimpleError::new(start, message));
+                    }
+                }
+            }
+            char::from_u32(code).ok_or_else(|| {
+                let message = format!("invalid unicode escape \\u{{{:x}}} at {}", code, start);
+                SimpleError::new(start, message)
+            })
+        }
+        c if c == quote => Ok(c),
+        c => {
+            let message = format!("unknown escape \\{} at {}", c, start);
+            Err(SimpleError::new(start, message))
+        }
+    }
+}
+
+fn quoted_body(state: &mut State<char>, start:usize, quote:char) -> Status<String> {
+    let mut re = String::new();
+    loop {
+        let pos = state.pos();
+        match state.next() {
+            None => {
+                let message = format!("unterminated string literal start at {}", start);
+                return Err(SimpleError::new(start, message));
+            }
+            Some('\\') => {
+                match escape(state, pos, quote) {
+                    Ok(c) => re.push(c),
+                    Err(err) => return Err(err),
+                }
+            }
+            Some(c) if c == quote => return Ok(re),
+            Some(c) => re.push(c),
+        }
+    }
+}
+
+// raw string as r#"..."#, any count of # but must be same at both sides.
+fn raw_body(state: &mut State<char>, start:usize, quote:char) -> Status<String> {
+    let mut hashes = 0;
+    loop {
+        match state.next() {
+            Some('#') => hashes += 1,
+            Some(c) if c == quote => break,
+            _ => {
+                let message = format!("expect raw string literal at {}", start);
+                return Err(SimpleError::new(start, message));
+            }
+        }
+    }
+    let mut re = String::new();
+    loop {
+        match state.next() {
+            None => {
+                let message = format!("unterminated raw string literal start at {}", start);
+                return Err(SimpleError::new(start, message));
+            }
+            Some(c) if c == quote => {
+                let pos = state.pos();
+                let mut count = 0;
+                while count < hashes && state.next() == Some('#') {
+                    count += 1;
+                }
+                if count == hashes {
+                    return Ok(re);
+                }
+                state.seek_to(pos);
+                re.push(c);
+            }
+            Some(c) => re.push(c),
+        }
+    }
+}
+
+// Quoted string with escapes, or raw string like r#"..."# which never unescapes. It consumes
+// nothing if the input does not start with the quote or r# or r and the quote.
+pub fn string_literal(quote:char) -> Monad<char, (), String> {
+    let open = eq(quote);
+    let raw = look_ahead(arc!(try(arc!(eq('r').then(arc!(one_of(&vec!['#', quote])))))));
+    pack(()).bind(bnd!(move |state: &mut State<char>, _:()|->Status<String> {
+        let start = state.pos();
+        if open.parse(state).is_ok() {
+            quoted_body(state, start, quote)
+        } else if raw.parse(state).is_ok() {
+            state.next();
+            raw_body(state, start, quote)
+        } else {
+            Err(expect(state, "string literal"))
+        }
+    }))
+}
+
+// Char literal as 'a' or '\n', it consumes nothing if the input does not start with '.
+pub fn char_literal() -> Monad<char, (), char> {
+    let open = eq('\'');
+    pack(()).bind(bnd!(move |state: &mut State<char>, _:()|->Status<char> {
+        let start = state.pos();
+        if open.parse(state).is_err() {
+            return Err(expect(state, "char literal"));
+        }
+        let pos = state.pos();
+        let content = match state.next() {
+            Some('\\') => escape(state, pos, '\''),
+            Some('\'') => {
+                let message = format!("empty char literal at {}", start);
+                Err(SimpleError::new(start, message))
+            }
+            Some(c) => Ok(c),
+            None => {
+                let message = format!("unterminated char literal start at {}", start);
+                Err(SimpleError::new(start, message))
+            }
+        };
+        content.and_then(|c:char| {
+            if state.next() == Some('\'') {
+                Ok(c)
+            } else {
+                let message = format!("unterminated char literal start at {}", start);
+                Err(SimpleError::new(start, message))
+            }
+        })
+    }))
+}
+
+// Accept "+" or "-", return true if it is negative.
+fn sign(state: &mut State<char>) -> bool {
+    match peek(state) {
+        Some('-') => {
+            state.next();
+            true
+        }
+        Some('+') => {
+            state.next();
+            false
+        }
+        _ => false,
+    }
+}
+
+// Digits in radix, `_` is allowed as separator with a digit on each side and dropped.
+fn radix_digits(state: &mut State<char>, radix:u32) -> String {
+    let mut re = String::new();
+    loop {
+        let pos = state.pos();
+        match state.next() {
+            Some('_') if !re.is_empty() && peek(state).map_or(false, |c:char| c.is_digit(radix)) => (),
+            Some(c) if c.is_digit(radix) => re.push(c),
+            _ => {
+                state.seek_to(pos);
+                return re;
+            }
+        }
+    }
+}
+
+// Prefix 0x, 0o or 0b select radix, or decimal.
+fn radix_prefix(state: &mut State<char>) -> u32 {
+    let pos = state.pos();
+    if state.next() == Some('0') {
+        let radix = match state.next() {
+            Some('x') | Some('X') => 16,
+            Some('o') | Some('O') => 8,
+            Some('b') | Some('B') => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            return radix;
+        }
+    }
+    state.seek_to(pos);
+    10
+}
+
+// Unsigned magnitude with radix prefix, start is the position of whole number for errors.
+fn magnitude(state: &mut State<char>, start:usize) -> Status<u64> {
+    let radix = radix_prefix(state);
+    let digits = radix_digits(state, radix);
+    if digits.is_empty() {
+        let pos = state.pos();
+        state.seek_to(start);
+        let message = format!("expect digits at {}", pos);
+        return Err(SimpleError::new(pos, message));
+    }
+    let mut re:u64 = 0;
+    for c in digits.chars() {
+        let d = c.to_digit(radix).unwrap() as u64;
+        match re.checked_mul(radix as u64).and_then(|x:u64| x.checked_add(d)) {
+            Some(x) => re = x,
+            None => {
+                let message = format!("integer literal at {} overflow", start);
+                return Err(SimpleError::new(start, message));
+            }
+        }
+    }
+    Ok(re)
+}
+
+fn signed(state: &mut State<char>, min:i64, max:i64) -> Status<i64> {
+    let start = state.pos();
+    let negative = sign(state);
+    magnitude(state, start).and_then(|x:u64| {
+        let limit = if negative { (-(min + 1)) as u64 + 1 } else { max as u64 };
+        if x > limit {
+            let message = format!("integer literal at {} out of range [{}, {}]", start, min, max);
+            Err(SimpleError::new(start, message))
+        } else if negative && x > 0 {
+            Ok(-((x - 1) as i64) - 1)
+        } else if negative {
+            Ok(0)
+        } else {
+            Ok(x as i64)
+        }
+    })
+}
+
+pub fn uint64() -> Monad<char, (), u64> {
+    scan(bnd!(|state: &mut State<char>|->Status<u64> {
+        let start = state.pos();
+        magnitude(state, start)
+    }))
+}
+
+pub fn int64() -> Monad<char, (), i64> {
+    scan(bnd!(|state: &mut State<char>|->Status<i64> {
+        signed(state, i64::MIN, i64::MAX)
+    }))
+}
+
+pub fn int32() -> Monad<char, (), i32> {
+    scan(bnd!(|state: &mut State<char>|->Status<i32> {
+        signed(state, i32::MIN as i64, i32::MAX as i64).map(|x:i64| x as i32)
+    }))
+}
+
+// Match a word without case, restore state if mismatch. The word must not be followed by a
+// letter, digit or `_`.
+fn keyword(state: &mut State<char>, word:&str) -> bool {
+    let pos = state.pos();
+    for w in word.chars() {
+        match state.next() {
+            Some(c) if c.to_ascii_lowercase() == w => (),
+            _ => {
+                state.seek_to(pos);
+                return false;
+            }
+        }
+    }
+    // A word must end here, "information" is not "inf".
+    if peek(state).map_or(false, |c:char| c.is_alphanumeric() || c == '_') {
+        state.seek_to(pos);
+        return false;
+    }
+    true
+}
+
+// Decimal float with `_` separators, fraction, exponent, and inf/infinity/nan.
+pub fn float64() -> Monad<char, (), f64> {
+    scan(bnd!(|state: &mut State<char>|->Status<f64> {
+        let start = state.pos();
+        let negative = sign(state);
+        if keyword(state, "infinity") || keyword(state, "inf") {
+            return Ok(if negative { f64::NEG_INFINITY } else { f64::INFINITY });
+        }
+        if keyword(state, "nan") {
+            return Ok(f64::NAN);
+        }
+        let mut literal = String::new();
+        if negative {
+            literal.push('-');
+        }
+        let int = radix_digits(state, 10);
+        literal.push_str(int.as_str());
+        let dot = state.pos();
+        let mut frac = String::new();
+        if state.next() == Some('.') {
+            frac = radix_digits(state, 10);
+        }
+        if frac.is_empty() {
+            state.seek_to(dot);
+        } else {
+            literal.push('.');
+            literal.push_str(frac.as_str());
+        }
+        if int.is_empty() && frac.is_empty() {
+            let pos = state.pos();
+            state.seek_to(start);
+            let message = format!("expect float at {}", pos);
+            return Err(SimpleError::new(pos, message));
+        }
+        let exp = state.pos();
+        match state.next() {
+            Some('e') | Some('E') => {
+                let mut exponent = String::from("e");
+                if sign(state) {
+                    exponent.push('-');
+                }
+                let digits = radix_digits(state, 10);
+                if digits.is_empty() {
+                    state.seek_to(exp);
+                } else {
+                    exponent.push_str(digits.as_str());
+                    literal.push_str(exponent.as_str());
+                }
+            }
+            _ => {
+                state.seek_to(exp);
+            }
+        }
+        let re = literal.parse::<f64>().unwrap();
+        if re.is_infinite() {
+            let message = format!("float literal at {} overflow", start);
+            Err(SimpleError::new(start, message))
+        } else {
+            Ok(re)
+        }
+    }))
+}
+
+// Arbitrary precision integer, enable it by feature "bigint".
+#[cfg(feature = "bigint")]
+pub fn big_integer() -> Monad<char, (), BigInt> {
+    scan(bnd!(|state: &mut State<char>|->Status<BigInt> {
+        let start = state.pos();
+        let negative = sign(state);
+        let radix = radix_prefix(state);
+        let digits = radix_digits(state, radix);
+        if digits.is_empty() {
+            let pos = state.pos();
+            state.seek_to(start);
+            let message = format!("expect digits at {}", pos);
+            return Err(SimpleError::new(pos, message));
+        }
+        let re = BigInt::parse_bytes(digits.as_bytes(), radix).unwrap();
+        Ok(if negative { -re } else { re })
+    }))
+}
+
+pub fn spaces() -> Skip<char, char> {
+    skip_many(arc!(white_space()))
+}
+
+pub fn spaces1() -> Skip1<char, char> {
+    skip_many1(arc!(white_space()))
+}
+
+// Comment from prefix to end of line, the newline is left for white space parsers.
+pub fn line_comment(prefix:&str) -> Monad<char, Vec<char>, ()> {
+    string(prefix).then(arc!(skip_many(arc!(ne('\n'))))).then(arc!(pack(())))
+}
+
+// Comment between open and close, nested comment need close for every open.
+pub fn block_comment(open:&str, close:&str, nested:bool) -> Monad<char, String, ()> {
+    let open = String::from(open);
+    let close = String::from(close);
+    let start_tag = string(open.as_str());
+    start_tag.bind(bnd!(move |state: &mut State<char>, _:String|->Status<()> {
+        let start = state.pos() - open.chars().count();
+        let open_tag = string(open.as_str());
+        let close_tag = string(close.as_str());
+        let mut depth = 1;
+        loop {
+            if close_tag.parse(state).is_ok() {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if nested && open_tag.parse(state).is_ok() {
+                depth += 1;
+            } else if state.next().is_none() {
+                let message = format!("unterminated block comment start at {}", start);
+                return Err(SimpleError::new(start, message));
+            }
+        }
+    }))
+}
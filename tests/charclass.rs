@@ -0,0 +1,56 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Error};
+use ruskell::parsec::atom::satisfy;
+use ruskell::parsec::combinator::many1;
+use ruskell::parsec::charclass::{Category, ScriptBlocks, range, chars, category, script_blocks};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+#[test]
+fn satisfy_test_0() {
+    let mut state = VecState::from_iter("a1".chars());
+    let p = satisfy(Arc::new(Box::new(|c:&char| *c == 'a' || *c == 'b')), String::from("a or b"));
+    assert_eq!(p.parse(&mut state).unwrap(), 'a');
+    let err = p.parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 1);
+    assert!(err.message().contains("a or b"));
+    assert_eq!(state.pos(), 1);
+}
+
+#[test]
+fn char_class_test_0() {
+    let ident = range('a', 'z').union(range('A', 'Z')).union(chars("_"));
+    assert!(ident.contains('q'));
+    assert!(ident.contains('_'));
+    assert!(!ident.contains('1'));
+    assert!(ident.clone().negate().contains('1'));
+    let mut state = VecState::from_iter("_x-".chars());
+    let p = many1(Arc::new(ident.parser()));
+    assert_eq!(p.parse(&mut state).unwrap(), vec!['_', 'x']);
+}
+
+#[test]
+fn char_class_test_1() {
+    assert!(category(Category::Lu).contains('Ä'));
+    assert!(!category(Category::Lu).contains('ä'));
+    assert!(category(Category::Nd).contains('٣'));
+    assert!(!category(Category::Nd).contains('½'));
+    assert!(script_blocks(ScriptBlocks::Greek).contains('λ'));
+    assert!(script_blocks(ScriptBlocks::Han).contains('中'));
+    assert!(!script_blocks(ScriptBlocks::Latin).contains('中'));
+}
+
+#[test]
+fn char_class_test_2() {
+    // General categories, not the wider char properties of std.
+    assert!(!category(Category::Lu).contains('Ⓐ'));
+    assert!(!category(Category::Ll).contains('ª'));
+    assert!(category(Category::L).contains('ǅ'));
+    assert!(category(Category::P).contains('᳀'));
+    assert!(category(Category::Nd).contains('𝟘'));
+    assert!(category(Category::N).contains('Ⅻ'));
+    assert!(category(Category::Zs).contains('\u{3000}'));
+    assert!(!category(Category::Zs).contains('\t'));
+    assert!(category(Category::Cc).contains('\u{7f}'));
+    assert_eq!(format!("{}", script_blocks(ScriptBlocks::Greek)), "\\p{Greek blocks}");
+}
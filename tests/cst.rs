@@ -0,0 +1,69 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Status, Parsec, M};
+use ruskell::parsec::atom::string;
+use ruskell::parsec::combinator::{many1, between, sep_by, optional};
+use ruskell::parsec::lexeme::{lexeme, symbol};
+use ruskell::parsec::text::{digit, alpha, spaces, line_comment};
+use ruskell::parsec::cst::{SyntaxNode, SyntaxElement, SyntaxToken, Spanned, node, span, token, trivia};
+use std::sync::Arc;
+use std::iter::FromIterator;
+
+fn list() -> Arc<Parsec<char, SyntaxNode>> {
+    let number = node("number", arc!(many1(arc!(digit()))));
+    let items = sep_by(arc!(symbol(",")), arc!(lexeme(arc!(number))));
+    arc!(node("list", arc!(between(arc!(symbol("[")), arc!(items), arc!(symbol("]"))))))
+}
+
+#[test]
+fn node_test_0() {
+    let source = "[1, 22 ,3]";
+    let mut state = VecState::from_iter(source.chars());
+    let root = list().parse(&mut state).unwrap();
+    assert_eq!(root.text(), source);
+    assert_eq!(root.pretty(), "list@0..10
+  text@0..1 \"[\"
+  number@1..2
+    text@1..2 \"1\"
+  text@2..3 \",\"
+  whitespace@3..4 \" \"
+  number@4..6
+    text@4..6 \"22\"
+  whitespace@6..7 \" \"
+  text@7..8 \",\"
+  number@8..9
+    text@8..9 \"3\"
+  text@9..10 \"]\"
+");
+    let numbers = root.find_all("number").iter().map(|n:&&SyntaxNode| n.text()).collect::<Vec<String>>();
+    assert_eq!(numbers, vec!["1", "22", "3"]);
+    assert_eq!(root.node_at(5).unwrap().text(), "22");
+    assert_eq!(root.node_at(6).unwrap().name, "list");
+    assert_eq!(root.tokens().len(), 9);
+}
+
+#[test]
+fn token_test_0() {
+    let source = "abc  # note";
+    let word = token("word", arc!(many1(arc!(alpha()))));
+    let comment = optional(arc!(trivia("comment", arc!(line_comment("#")))));
+    let line = word.bind(bnd!(move |state:&mut State<char>, w:SyntaxElement|->Status<(SyntaxElement, Option<SyntaxElement>)> {
+        spaces().parse(state).unwrap();
+        comment.parse(state).map(|c:Option<SyntaxElement>| (w.clone(), c))
+    }));
+    let mut state = VecState::from_iter(source.chars());
+    let root = node("line", arc!(line)).parse(&mut state).unwrap();
+    assert_eq!(format!("{}", root), source);
+    let kinds = root.tokens().iter().map(|t:&&SyntaxToken| t.kind.clone()).collect::<Vec<String>>();
+    assert_eq!(kinds, vec!["word", "whitespace", "comment"]);
+    assert!(root.children[2].is_trivia());
+    assert!(!root.children[0].is_trivia());
+}
+
+#[test]
+fn span_test_0() {
+    let mut state = VecState::from_iter("xab".chars());
+    state.next();
+    let re = span(arc!(string("ab"))).parse(&mut state).unwrap();
+    assert_eq!(re, Spanned{value:String::from("ab"), start:1, end:3});
+}
@@ -0,0 +1,46 @@
+extern crate ruskell;
+use ruskell::formats::csv::{CsvConfig, CsvReader, parse};
+use ruskell::parsec::{VecState, Error};
+use std::iter::FromIterator;
+
+fn row(items:&[&str]) -> Vec<String> {
+    items.iter().map(|x:&&str| String::from(*x)).collect()
+}
+
+#[test]
+fn csv_test_0() {
+    let csv = parse("a,b,c\r\n1,\"x, \"\"y\"\"\",\n", &CsvConfig::new()).unwrap();
+    assert_eq!(csv.headers, None);
+    assert_eq!(csv.records, vec![row(&["a", "b", "c"]), row(&["1", "x, \"y\"", ""])]);
+}
+
+#[test]
+fn csv_test_1() {
+    let csv = parse("name,note\nbob,\"multi\nline\"\nalice,plain", &CsvConfig::new().header(true)).unwrap();
+    assert_eq!(csv.headers, Some(row(&["name", "note"])));
+    assert_eq!(csv.records, vec![row(&["bob", "multi\nline"]), row(&["alice", "plain"])]);
+}
+
+#[test]
+fn tsv_test_0() {
+    let config = CsvConfig::tsv().quote('\'').escape('\\');
+    let csv = parse("a\t'b\\'c'\r", &config).unwrap();
+    assert_eq!(csv.records, vec![row(&["a", "b'c"])]);
+}
+
+#[test]
+fn csv_error_test_0() {
+    let err = parse("a,b\nc,\"d\"e\n", &CsvConfig::new()).unwrap_err();
+    assert_eq!(err.pos(), 9);
+}
+
+#[test]
+fn csv_reader_test_0() {
+    let mut state = VecState::from_iter("h1;h2\n1;2\n3;4\n".chars());
+    let config = CsvConfig::new().delimiter(';').header(true);
+    let mut reader = CsvReader::new(&mut state, &config).unwrap();
+    assert_eq!(reader.headers(), Some(&row(&["h1", "h2"])));
+    assert_eq!(reader.next().unwrap().unwrap(), row(&["1", "2"]));
+    assert_eq!(reader.next().unwrap().unwrap(), row(&["3", "4"]));
+    assert!(reader.next().is_none());
+}
@@ -0,0 +1,96 @@
+extern crate ruskell;
+use ruskell::formats::datetime::{Date, Time, Offset, DateTime, Duration, DateFormat};
+use ruskell::formats::datetime::{iso_date, iso_time, iso_offset, iso_datetime, iso_duration, rfc3339, rfc2822, strptime};
+use ruskell::parsec::{VecState, Parsec, Error};
+use std::iter::FromIterator;
+
+fn state(text:&str) -> VecState<char> {
+    VecState::from_iter(text.chars())
+}
+
+#[test]
+fn iso_date_time_test_0() {
+    assert_eq!(iso_date().parse(&mut state("2024-02-29")).unwrap(), Date{year:2024, month:2, day:29});
+    assert_eq!(iso_date().parse(&mut state("19991231")).unwrap(), Date{year:1999, month:12, day:31});
+    assert_eq!(iso_time().parse(&mut state("23:59:60,25")).unwrap(),
+               Time{hour:23, minute:59, second:60, nanosecond:250000000});
+    assert_eq!(iso_time().parse(&mut state("0830")).unwrap(), Time{hour:8, minute:30, second:0, nanosecond:0});
+    assert_eq!(iso_offset().parse(&mut state("-0530")).unwrap(), Offset{minutes:-330});
+    assert_eq!(iso_offset().parse(&mut state("+08")).unwrap(), Offset{minutes:480});
+    let dt = iso_datetime().parse(&mut state("2007-04-05T14:30")).unwrap();
+    assert_eq!(dt.offset, None);
+    assert_eq!(format!("{}", dt), "2007-04-05T14:30:00");
+}
+
+#[test]
+fn rfc3339_test_0() {
+    let dt = rfc3339().parse(&mut state("1985-04-12T23:20:50.52Z")).unwrap();
+    assert_eq!(dt.time.nanosecond, 520000000);
+    assert_eq!(format!("{}", dt), "1985-04-12T23:20:50.52Z");
+    assert_eq!(dt.timestamp(), Some(482196050));
+    let dt = rfc3339().parse(&mut state("1996-12-19 16:39:57-08:00")).unwrap();
+    assert_eq!(dt.offset, Some(Offset{minutes:-480}));
+    assert_eq!(dt.timestamp(), Some(851042397));
+    let err = rfc3339().parse(&mut state("1996-12-19T16:39-08:00")).unwrap_err();
+    assert_eq!(err.pos(), 16);
+    let err = rfc3339().parse(&mut state("1996-12-19T16:39:57")).unwrap_err();
+    assert_eq!(err.pos(), 19);
+}
+
+#[test]
+fn datetime_range_error_test_0() {
+    let err = iso_date().parse(&mut state("2023-02-29")).unwrap_err();
+    assert_eq!(err.pos(), 8);
+    assert!(err.message().contains("day 29 out of range 1..28"));
+    let err = iso_date().parse(&mut state("2023-13-01")).unwrap_err();
+    assert_eq!(err.pos(), 5);
+    let err = rfc3339().parse(&mut state("2023-01-01T10:61:00Z")).unwrap_err();
+    assert_eq!(err.pos(), 14);
+    let err = iso_offset().parse(&mut state("+24:00")).unwrap_err();
+    assert_eq!(err.pos(), 1);
+}
+
+#[test]
+fn rfc2822_test_0() {
+    let dt = rfc2822().parse(&mut state("Fri, 21 Nov 1997 09:55:06 -0600")).unwrap();
+    assert_eq!(dt, DateTime{date:Date{year:1997, month:11, day:21},
+                            time:Time{hour:9, minute:55, second:6, nanosecond:0},
+                            offset:Some(Offset{minutes:-360})});
+    let dt = rfc2822().parse(&mut state("1 jan 2000 00:00 GMT")).unwrap();
+    assert_eq!(dt.timestamp(), Some(946684800));
+    let dt = rfc2822().parse(&mut state("Tue, 1 Jul 2003 10:52:37 PDT")).unwrap();
+    assert_eq!(dt.offset, Some(Offset{minutes:-420}));
+    let err = rfc2822().parse(&mut state("Sat, 21 Nov 1997 09:55:06 -0600")).unwrap_err();
+    assert_eq!(err.pos(), 0);
+    let err = rfc2822().parse(&mut state("21 Foo 1997 09:55:06 -0600")).unwrap_err();
+    assert_eq!(err.pos(), 3);
+}
+
+#[test]
+fn iso_duration_test_0() {
+    let d = iso_duration().parse(&mut state("P1Y2M10DT2H30M1.5S")).unwrap();
+    assert_eq!(d, Duration{years:1, months:2, weeks:0, days:10, hours:2, minutes:30, seconds:1.5});
+    assert_eq!(format!("{}", d), "P1Y2M10DT2H30M1.5S");
+    assert_eq!(format!("{}", iso_duration().parse(&mut state("P3W")).unwrap()), "P3W");
+    assert_eq!(iso_duration().parse(&mut state("PT36H")).unwrap().hours, 36);
+    assert_eq!(iso_duration().parse(&mut state("P")).unwrap_err().pos(), 1);
+    assert_eq!(iso_duration().parse(&mut state("P1DT")).unwrap_err().pos(), 4);
+    assert_eq!(iso_duration().parse(&mut state("P1D2Y")).unwrap_err().pos(), 4);
+    assert_eq!(iso_duration().parse(&mut state("P1.5D")).unwrap_err().pos(), 2);
+}
+
+#[test]
+fn strptime_test_0() {
+    let p = strptime("%d/%b/%Y:%H:%M:%S %z");
+    let dt = p.parse(&mut state("10/Oct/2000:13:55:36 -0700")).unwrap();
+    assert_eq!(format!("{}", dt), "2000-10-10T13:55:36-07:00");
+    let p = strptime("%a %Y%m%d %H.%f%%");
+    let dt = p.parse(&mut state("Mon 20240101 07.125%")).unwrap();
+    assert_eq!(dt.time, Time{hour:7, minute:0, second:0, nanosecond:125000000});
+    let err = p.parse(&mut state("Tue 20240101 07.125%")).unwrap_err();
+    assert_eq!(err.pos(), 0);
+    let err = strptime("%Y-%m-%d").parse(&mut state("2023-04-31")).unwrap_err();
+    assert_eq!(err.pos(), 8);
+    assert!(DateFormat::new("%Q").is_err());
+    assert!(DateFormat::new("%").is_err());
+}
@@ -0,0 +1,51 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Error};
+use ruskell::parsec::text::digit;
+use std::iter::FromIterator;
+
+fn fold(x:i64, rest:Vec<(char, i64)>) -> i64 {
+    rest.into_iter().fold(x, |acc:i64, (op, y):(char, i64)| match op {
+        '+' => acc + y,
+        '-' => acc - y,
+        '*' => acc * y,
+        _ => acc / y,
+    })
+}
+
+grammar! {
+    number: i64 = '-'? [digit()]+ => |(sign, ds):(Option<char>, Vec<char>)| {
+        let x = ds.into_iter().collect::<String>().parse::<i64>().unwrap();
+        if sign.is_some() { -x } else { x }
+    };
+    factor: i64 = number | ('(' expr ')' => |(_, x, _):(char, i64, char)| x);
+    term: i64 = factor (('*' | '/') factor)* => |(x, rest):(i64, Vec<(char, i64)>)| fold(x, rest);
+    expr: i64 = term (('+' | '-') term)* => |(x, rest):(i64, Vec<(char, i64)>)| fold(x, rest);
+}
+
+grammar! {
+    keyword: String = "let" | "loop" | "if";
+    pair: (String, char, char) = keyword ' ' [digit()];
+}
+
+fn state(text:&str) -> VecState<char> {
+    VecState::from_iter(text.chars())
+}
+
+#[test]
+fn grammar_calculator_test_0() {
+    assert_eq!(expr().parse(&mut state("1+2*3")).unwrap(), 7);
+    assert_eq!(expr().parse(&mut state("(1+2)*3-10/5")).unwrap(), 7);
+    assert_eq!(expr().parse(&mut state("-4*((2))")).unwrap(), -8);
+    let mut s = state("2*(3+4");
+    assert_eq!(expr().parse(&mut s).unwrap(), 2);
+    assert_eq!(s.pos(), 1);
+}
+
+#[test]
+fn grammar_literal_test_0() {
+    assert_eq!(keyword().parse(&mut state("loop")).unwrap(), "loop");
+    assert_eq!(pair().parse(&mut state("if 7")).unwrap(), (String::from("if"), ' ', '7'));
+    let err = keyword().parse(&mut state("for")).unwrap_err();
+    assert_eq!(err.pos(), 0);
+}
@@ -0,0 +1,84 @@
+extern crate ruskell;
+use ruskell::formats::http::{Parsed, Header, Request, parse_request, parse_response, parse_chunked, request_head};
+use ruskell::parsec::{VecState, Parsec, Error};
+use std::iter::FromIterator;
+
+fn header(name:&str, value:&str) -> Header {
+    Header{name:String::from(name), value:String::from(value)}
+}
+
+#[test]
+fn http_request_test_0() {
+    let raw = b"\r\nGET /index.html?q=1 HTTP/1.1\r\nHost: example.com\r\nAccept:  */*  \r\n\r\nbody";
+    let expected = Request{method:String::from("GET"), target:String::from("/index.html?q=1"), version:(1, 1),
+                           headers:vec![header("Host", "example.com"), header("Accept", "*/*")]};
+    assert_eq!(parse_request(raw).unwrap(), Parsed::Complete(expected, raw.len() - 4));
+    match parse_request(b"POST / HTTP/1.0\nContent-Length: 3\n\n").unwrap() {
+        Parsed::Complete(request, _) => {
+            assert_eq!(request.version, (1, 0));
+            assert_eq!(request.header("content-length"), Some("3"));
+        }
+        Parsed::Incomplete => panic!("request should be complete"),
+    }
+}
+
+#[test]
+fn http_response_test_0() {
+    let raw = b"HTTP/1.1 200 OK\r\nX-Long: first\r\n  second\r\n\tthird\r\nServer: t\r\n\r\n";
+    match parse_response(raw).unwrap() {
+        Parsed::Complete(response, consumed) => {
+            assert_eq!(consumed, raw.len());
+            assert_eq!((response.status, response.reason.as_str()), (200, "OK"));
+            assert_eq!(response.headers, vec![header("X-Long", "first second third"), header("Server", "t")]);
+        }
+        Parsed::Incomplete => panic!("response should be complete"),
+    }
+    match parse_response(b"HTTP/1.1 204\r\n\r\n").unwrap() {
+        Parsed::Complete(response, _) => assert_eq!((response.status, response.reason.as_str()), (204, "")),
+        Parsed::Incomplete => panic!("response should be complete"),
+    }
+}
+
+#[test]
+fn http_incomplete_test_0() {
+    let raw = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+    for end in 0..raw.len() {
+        assert_eq!(parse_request(&raw[..end]).unwrap(), Parsed::Incomplete);
+    }
+    assert!(parse_request(raw).is_ok());
+    assert_eq!(parse_response(b"HTTP/1.1 20").unwrap(), Parsed::Incomplete);
+}
+
+#[test]
+fn http_error_test_0() {
+    assert_eq!(parse_request(b"GET / HTTP/1.1\r\nBad Header: x\r\n\r\n").unwrap_err().pos(), 19);
+    assert_eq!(parse_request(b"GET / HTTP/1.1\r\n folded: x\r\n\r\n").unwrap_err().pos(), 16);
+    assert_eq!(parse_request(b"GET / HTTX/1.1\r\n\r\n").unwrap_err().pos(), 9);
+    assert_eq!(parse_response(b"HTTP/1.1 2x0 OK\r\n\r\n").unwrap_err().pos(), 10);
+    assert_eq!(parse_request(b"GET / HTTP/1.1\r\nA: b\x01\r\n\r\n").unwrap_err().pos(), 20);
+}
+
+#[test]
+fn http_chunked_test_0() {
+    let raw = b"4\r\nWiki\r\n5;ext=1\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\n";
+    match parse_chunked(raw).unwrap() {
+        Parsed::Complete((body, trailers), consumed) => {
+            assert_eq!(body, b"Wikipedia in\r\n\r\nchunks.".to_vec());
+            assert_eq!(trailers, vec![header("Expires", "never")]);
+            assert_eq!(consumed, raw.len());
+        }
+        Parsed::Incomplete => panic!("body should be complete"),
+    }
+    for end in 0..raw.len() {
+        assert_eq!(parse_chunked(&raw[..end]).unwrap(), Parsed::Incomplete);
+    }
+    assert_eq!(parse_chunked(b"4\r\nWikiXX\r\n").unwrap_err().pos(), 7);
+    assert_eq!(parse_chunked(b"G\r\n").unwrap_err().pos(), 0);
+}
+
+#[test]
+fn http_state_test_0() {
+    let mut state = VecState::from_iter(b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\n".iter().cloned());
+    assert_eq!(request_head().parse(&mut state).unwrap().target, "/a");
+    assert_eq!(request_head().parse(&mut state).unwrap().target, "/b");
+}
@@ -0,0 +1,84 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{SimpleError, Error, Parsec, M, parser};
+use ruskell::parsec::atom::eof;
+use ruskell::parsec::combinator::many1;
+use ruskell::parsec::grammar::{seq4, star};
+use ruskell::parsec::lexeme::{lexeme, symbol};
+use ruskell::parsec::text::{digit, alpha};
+use ruskell::parsec::cst::SyntaxNode;
+use ruskell::parsec::incremental::{Reparser, Edit};
+use std::sync::Arc;
+
+// doc = stmt*, stmt = name '=' number ';'
+fn document(re:&Reparser) -> Arc<Parsec<char, SyntaxNode>> {
+    let name = re.rule("name", arc!(lexeme(arc!(many1(arc!(alpha()))))));
+    let number = re.rule("number", arc!(lexeme(arc!(many1(arc!(digit()))))));
+    let stmt = re.rule("stmt", seq4(name, arc!(symbol("=")), number, arc!(symbol(";"))));
+    re.rule("doc", arc!(parser(star(stmt)).over(arc!(eof()))))
+}
+
+fn full(text:&str) -> Result<SyntaxNode, usize> {
+    let re = Reparser::new();
+    re.parse(&*document(&re), text).map_err(|err:SimpleError| err.pos())
+}
+
+#[test]
+fn edit_test_0() {
+    let edit = Edit::new(2, 4, "xyz");
+    assert_eq!(edit.apply("abcdef").unwrap(), "abxyzef");
+    assert_eq!(Edit::new(6, 6, "!").apply("abcdef").unwrap(), "abcdef!");
+    let err = Edit::new(5, 40, "").apply("abcdef").unwrap_err();
+    assert_eq!(err.pos(), 5);
+    assert_eq!(err.message(), "edit 5..40 out of text length 6");
+    assert!(Edit::new(4, 2, "").apply("abcdef").is_err());
+}
+
+#[test]
+fn reparse_test_0() {
+    let re = Reparser::new();
+    let doc = document(&re);
+    let text = "a=1;\nb=22;\nc=3;\n";
+    assert_eq!(re.parse(&*doc, text).unwrap().text(), text);
+    let all = re.stats();
+    assert_eq!(all.reused, 0);
+    let tree = re.reparse(&*doc, &Edit::new(7, 9, "305")).unwrap();
+    assert_eq!(re.text(), "a=1;\nb=305;\nc=3;\n");
+    assert_eq!(Ok(tree.clone()), full(re.text().as_str()));
+    assert_eq!(tree.find_all("number")[1].text(), "305");
+    // stmt a, stmt c and name b are reused, only the rules cover the edit run again.
+    let stats = re.stats();
+    assert_eq!(stats.reused, 3);
+    assert!(stats.parsed < all.parsed);
+}
+
+#[test]
+fn reparse_test_1() {
+    let re = Reparser::new();
+    let doc = document(&re);
+    re.parse(&*doc, "a=1;\nb=2;\n").unwrap();
+    let edits = vec![
+        Edit::new(0, 0, "x=0;\n"),
+        Edit::new(15, 15, "c = 7 ;"),
+        Edit::new(6, 7, "yy"),
+        Edit::new(4, 11, ""),
+        Edit::new(3, 3, "9"),
+    ];
+    for edit in edits.iter() {
+        let tree = re.reparse(&*doc, edit);
+        assert_eq!(tree.map_err(|err:SimpleError| err.pos()), full(re.text().as_str()));
+    }
+    assert_eq!(re.text(), "x=09;b=2;\nc = 7 ;");
+}
+
+#[test]
+fn reparse_test_2() {
+    let re = Reparser::new();
+    let doc = document(&re);
+    re.parse(&*doc, "a=1;\nb=2;\n").unwrap();
+    let err = re.reparse(&*doc, &Edit::new(8, 9, "")).unwrap_err();
+    assert_eq!(Err(err.pos()), full(re.text().as_str()));
+    let tree = re.reparse(&*doc, &Edit::new(8, 8, ";")).unwrap();
+    assert_eq!(Ok(tree), full("a=1;\nb=2;\n"));
+    assert!(re.reparse(&*doc, &Edit::new(5, 40, "")).is_err());
+}
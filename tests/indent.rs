@@ -0,0 +1,78 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Status, Error, M, parser};
+use ruskell::parsec::combinator::{many1, skip_many};
+use ruskell::parsec::indent::{column, line, with_pos, indented, same_line, block, indent_block};
+use ruskell::parsec::text::{alpha, white_space};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+fn indent_leaf() -> Arc<Parsec<char, String>> {
+    let word = parser(Arc::new(many1(Arc::new(alpha())))).over(Arc::new(skip_many(Arc::new(white_space()))));
+    Arc::new(word.bind(Arc::new(Box::new(|_:&mut State<char>, x:Vec<char>|->Status<String> {
+        Ok(x.into_iter().collect::<String>())
+    }))))
+}
+
+#[test]
+fn indent_block_test_0() {
+    let mut state = VecState::from_iter("a\n  b\n  c\nd\n".chars());
+    let p = block(Arc::new(indent_block(indent_leaf(), indent_leaf())));
+    let re = p.parse(&mut state).unwrap();
+    assert_eq!(re, vec![(String::from("a"), vec![String::from("b"), String::from("c")]),
+                        (String::from("d"), vec![])]);
+    assert_eq!(state.indents(), Some(&mut vec![]));
+}
+
+#[test]
+fn indent_block_test_1() {
+    let mut state = VecState::from_iter("a\n  b\n    c\n".chars());
+    let p = block(Arc::new(indent_block(indent_leaf(), indent_leaf())));
+    let err = p.parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 10);
+    assert!(err.message().contains("unexpected indentation at line 3 column 5"));
+}
+
+#[test]
+fn indent_check_test_0() {
+    let mut state = VecState::from_iter("ab\n  cd".chars());
+    let p = with_pos(Arc::new(parser(indent_leaf()).then(Arc::new(indented())).then(indent_leaf())));
+    assert_eq!(p.parse(&mut state).unwrap(), "cd");
+    let mut state = VecState::from_iter("ab\ncd".chars());
+    let p = with_pos(Arc::new(parser(indent_leaf()).then(Arc::new(indented())).then(indent_leaf())));
+    assert!(p.parse(&mut state).is_err());
+    let mut state = VecState::from_iter("ab cd".chars());
+    let p = with_pos(Arc::new(parser(indent_leaf()).then(Arc::new(same_line())).then(indent_leaf())));
+    assert_eq!(p.parse(&mut state).unwrap(), "cd");
+}
+
+#[test]
+fn indent_column_test_0() {
+    let mut state = VecState::from_iter("ab\n\tcd".chars());
+    assert_eq!(column(&mut state, 5), 3);
+    assert_eq!(line(&mut state, 5), 2);
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn indent_stack_test_0() {
+    // A failed block leaves no reference behind.
+    let mut state = VecState::from_iter("a\n  b\n    c\n".chars());
+    let p = block(Arc::new(indent_block(indent_leaf(), indent_leaf())));
+    assert!(p.parse(&mut state).is_err());
+    assert_eq!(state.indents(), Some(&mut vec![]));
+    let mut state = VecState::from_iter("ab\ncd".chars());
+    let p = with_pos(Arc::new(parser(indent_leaf()).then(Arc::new(same_line())).then(indent_leaf())));
+    assert!(p.parse(&mut state).is_err());
+    assert_eq!(state.indents(), Some(&mut vec![]));
+}
+
+#[test]
+fn indent_column_test_1() {
+    let mut state = VecState::from_iter("a\nb\n\ncd".chars());
+    state.seek_to(6);
+    assert_eq!(line(&mut state, 0), 1);
+    assert_eq!(column(&mut state, 0), 1);
+    assert_eq!(line(&mut state, 6), 4);
+    assert_eq!(column(&mut state, 6), 2);
+    assert_eq!(state.pos(), 6);
+}
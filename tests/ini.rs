@@ -0,0 +1,115 @@
+extern crate ruskell;
+use ruskell::formats::ini::{Value, Table, parse_toml, parse_ini};
+use ruskell::formats::datetime::{Date, Time, DateTime, Offset};
+use ruskell::parsec::Error;
+
+#[test]
+fn toml_test_0() {
+    let doc = parse_toml("# config
+title = \"demo\"  # trailing comment
+owner.name = 'Tom'
+
+[server]
+host = \"localhost\"
+port = 8_080
+ratio = 0.75
+enabled = true
+started = 1979-05-27T07:32:00Z
+ports = [ 8001, 8002,
+          8003, ]
+limits = { cpu = 2, mem = \"1G\" }
+
+[server.tls]
+cert = \"a.pem\"
+
+[[backend]]
+name = \"a\"
+
+[[backend]]
+name = \"b\"
+").unwrap();
+    assert_eq!(doc.get("title"), Some(&Value::String(String::from("demo"))));
+    assert_eq!(doc.get_path("owner.name"), Some(&Value::String(String::from("Tom"))));
+    assert_eq!(doc.get_path("server.port"), Some(&Value::Integer(8080)));
+    assert_eq!(doc.get_path("server.ratio"), Some(&Value::Float(0.75)));
+    assert_eq!(doc.get_path("server.enabled"), Some(&Value::Boolean(true)));
+    let started = DateTime{date:Date{year:1979, month:5, day:27},
+                           time:Time{hour:7, minute:32, second:0, nanosecond:0},
+                           offset:Some(Offset{minutes:0})};
+    assert_eq!(doc.get_path("server.started"), Some(&Value::Datetime(started)));
+    assert_eq!(doc.get_path("server.ports"),
+               Some(&Value::Array(vec![Value::Integer(8001), Value::Integer(8002), Value::Integer(8003)])));
+    assert_eq!(doc.get_path("server.limits.cpu"), Some(&Value::Integer(2)));
+    assert_eq!(doc.get_path("server.tls.cert"), Some(&Value::String(String::from("a.pem"))));
+    match doc.get("backend") {
+        Some(&Value::Array(ref items)) => {
+            assert_eq!(items.len(), 2);
+            let mut second = Table::new();
+            second.entries.push((String::from("name"), Value::String(String::from("b"))));
+            assert_eq!(items[1], Value::Table(second));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn toml_datetime_test_0() {
+    let doc = parse_toml("a = 1979-05-27 07:32:00.5\nb = 1979-05-27 # day\nc = 07:32:00\nd = 20240101\n").unwrap();
+    let time = Time{hour:7, minute:32, second:0, nanosecond:500000000};
+    let local = DateTime{date:Date{year:1979, month:5, day:27}, time:time, offset:None};
+    assert_eq!(doc.get("a"), Some(&Value::Datetime(local)));
+    assert_eq!(doc.get("b"), Some(&Value::Date(Date{year:1979, month:5, day:27})));
+    assert_eq!(doc.get("c"), Some(&Value::Time(Time{hour:7, minute:32, second:0, nanosecond:0})));
+    assert_eq!(doc.get("d"), Some(&Value::Integer(20240101)));
+    let err = parse_toml("a = 1979-02-30").unwrap_err();
+    assert!(err.message().contains("line 1 column 5"));
+}
+
+#[test]
+fn toml_string_escape_test_0() {
+    let doc = parse_toml("a = \"\\u00e9\\b\\t\\n\\f\\r\\\"\\\\\\U0001F600\"\n").unwrap();
+    assert_eq!(doc.get("a"), Some(&Value::String(String::from("\u{e9}\u{8}\t\n\u{c}\r\"\\\u{1F600}"))));
+    for text in vec!["a = \"\\x41\"", "a = \"\\0\"", "a = \"\\'\"", "a = \"\\uD800\"", "a = \"\\u00e\""] {
+        let err = parse_toml(text).unwrap_err();
+        assert_eq!(err.pos(), 5);
+    }
+    assert!(parse_toml("a = \"x\ny\"").is_err());
+}
+
+#[test]
+fn toml_error_test_0() {
+    let err = parse_toml("a = 1\nb = 2\na = 3\n").unwrap_err();
+    assert_eq!(err.pos(), 12);
+    assert!(err.message().contains("line 3 column 1"));
+    let err = parse_toml("[x]\nport = 80x\n").unwrap_err();
+    assert!(err.message().contains("line 2 column 8"));
+    let err = parse_toml("a = 99999999999999999999").unwrap_err();
+    assert_eq!(err.pos(), 4);
+    let err = parse_toml("a = \"x\" b = 1").unwrap_err();
+    assert!(err.message().contains("line 1 column 9"));
+}
+
+#[test]
+fn ini_test_0() {
+    let doc = parse_ini("; global
+name = demo app
+
+[database]
+host: db.local
+password = \"p;w#d\"
+# comment
+[empty]
+").unwrap();
+    assert_eq!(doc.get("name"), Some(&Value::String(String::from("demo app"))));
+    assert_eq!(doc.get_path("database.host"), Some(&Value::String(String::from("db.local"))));
+    assert_eq!(doc.get_path("database.password"), Some(&Value::String(String::from("p;w#d"))));
+    assert_eq!(doc.get("empty"), Some(&Value::Table(Table::new())));
+}
+
+#[test]
+fn ini_error_test_0() {
+    let err = parse_ini("[a]\nx = 1\n[a]\n").unwrap_err();
+    assert!(err.message().contains("line 3 column 1"));
+    let err = parse_ini("[a]\njust text\n").unwrap_err();
+    assert!(err.message().contains("line 2"));
+}
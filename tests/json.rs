@@ -0,0 +1,69 @@
+extern crate ruskell;
+use ruskell::formats::json::{JsonValue, JsonParser, parse};
+use ruskell::parsec::{VecState, Parsec, Error};
+use std::iter::FromIterator;
+
+#[test]
+fn json_scalar_test_0() {
+    assert_eq!(parse("null").unwrap(), JsonValue::Null);
+    assert_eq!(parse(" true ").unwrap(), JsonValue::Bool(true));
+    assert_eq!(parse("\"a\\u00e9\\ud83d\\ude00\"").unwrap(), JsonValue::String(String::from("aé\u{1F600}")));
+    assert_eq!(parse("-0.5e+10").unwrap(), JsonValue::Number(String::from("-0.5e+10")));
+}
+
+#[test]
+fn json_number_precision_test_0() {
+    let text = "12345678901234567890123456789.000000000000000000001";
+    let value = parse(text).unwrap();
+    assert_eq!(format!("{}", value), text);
+    assert_eq!(parse("42").unwrap().as_i64(), Some(42));
+}
+
+#[test]
+fn json_number_digit_test_0() {
+    // Only ASCII digits, other numeric chars are not part of a number.
+    assert_eq!(parse("1\u{b2}").unwrap_err().pos(), 1);
+    assert_eq!(parse("[\u{663}2]").unwrap_err().pos(), 1);
+    assert_eq!(parse("1.\u{661}").unwrap_err().pos(), 2);
+    assert_eq!(parse("1e\u{ff11}").unwrap_err().pos(), 2);
+}
+
+#[test]
+fn json_document_test_0() {
+    let value = parse("{\"name\": \"ruskell\", \"tags\": [1, 2.5, null], \"nested\": {\"ok\": false}}").unwrap();
+    assert_eq!(value.get("name").and_then(|v:&JsonValue| v.as_str()), Some("ruskell"));
+    assert_eq!(value.get("tags").and_then(|v:&JsonValue| v.as_array()).map(|v:&Vec<JsonValue>| v.len()), Some(3));
+    assert_eq!(value.get("nested").and_then(|v:&JsonValue| v.get("ok")), Some(&JsonValue::Bool(false)));
+}
+
+#[test]
+fn json_round_trip_test_0() {
+    let text = "{\"a\":[1,-2,3e5,{\"b\":\"x\\\"y\\\\z\\n\\u0001\"}],\"c\":{},\"d\":[],\"e\":true}";
+    let value = parse(text).unwrap();
+    let output = format!("{}", value);
+    assert_eq!(output, text);
+    assert_eq!(parse(output.as_str()).unwrap(), value);
+}
+
+#[test]
+fn json_error_position_test_0() {
+    // sep_by1 leaves a separator without item, so the error is at the missing ']'.
+    assert_eq!(parse("[1, 2,, 3]").unwrap_err().pos(), 5);
+    assert_eq!(parse("{\"a\" 1}").unwrap_err().pos(), 5);
+    assert_eq!(parse("[1, 2").unwrap_err().pos(), 5);
+    assert_eq!(parse("\"ab\\x\"").unwrap_err().pos(), 3);
+    assert_eq!(parse("01").unwrap_err().pos(), 1);
+    assert_eq!(parse("1.").unwrap_err().pos(), 2);
+    assert_eq!(parse("\"a\nb\"").unwrap_err().pos(), 2);
+    assert_eq!(parse("[tru]").unwrap_err().pos(), 1);
+}
+
+#[test]
+fn json_depth_test_0() {
+    let deep = format!("{}{}", (0..10).map(|_| "[").collect::<String>(), (0..10).map(|_| "]").collect::<String>());
+    let mut state = VecState::from_iter(deep.chars());
+    assert!(JsonParser::new().max_depth(10).parse(&mut state).is_ok());
+    let mut state = VecState::from_iter(deep.chars());
+    let err = JsonParser::new().max_depth(9).parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 9);
+}
@@ -0,0 +1,47 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Error, M};
+use ruskell::parsec::text::{alpha, spaces, spaces1, block_comment};
+use ruskell::parsec::lexeme::{Lexeme, lexeme, symbol};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+#[test]
+fn spaces_test_0() {
+    let mut state = VecState::from_iter(" \t\n x".chars());
+    assert!(spaces().parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 4);
+    assert!(spaces().parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 4);
+    assert!(spaces1().parse(&mut state).is_err());
+}
+
+#[test]
+fn block_comment_test_0() {
+    let mut state = VecState::from_iter("/* a /* b */ c */x".chars());
+    assert!(block_comment("/*", "*/", true).parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 17);
+    let mut state = VecState::from_iter("/* a /* b */ c */x".chars());
+    assert!(block_comment("/*", "*/", false).parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 12);
+    let mut state = VecState::from_iter("x /* a /* b */".chars());
+    state.seek_to(2);
+    let re = block_comment("/*", "*/", true).parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 2);
+}
+
+#[test]
+fn lexeme_test_0() {
+    let lex = Lexeme::new().line_comment("//").block_comment("{-", "-}", true);
+    let mut state = VecState::from_iter("let // comment\n {- a {- b -} -} x = 1".chars());
+    let p = lex.symbol("let").then(Arc::new(lex.lexeme(Arc::new(alpha())))).over(Arc::new(lex.symbol("=")));
+    assert_eq!(p.parse(&mut state).unwrap(), 'x');
+    assert_eq!(state.pos(), 36);
+}
+
+#[test]
+fn lexeme_test_1() {
+    let mut state = VecState::from_iter("( a )".chars());
+    let p = symbol("(").then(Arc::new(lexeme(Arc::new(alpha())))).over(Arc::new(symbol(")")));
+    assert_eq!(p.parse(&mut state).unwrap(), 'a');
+    assert_eq!(state.pos(), 5);
+}
@@ -2,8 +2,13 @@
 #[macro_use]
 extern crate ruskell;
 use ruskell::parsec::{VecState, State, Status, Parsec, Error, monad, M, parser};
-use ruskell::parsec::atom::{one, eq, eof, one_of, none_of, ne};
-use ruskell::parsec::combinator::{either, many, many1, between, many_tail, many1_tail};
+use ruskell::parsec::atom::{one, eq, eof, one_of, none_of, ne, tag, string, istring, one_of_str, pack, peek, expect, scan};
+use ruskell::parsec::combinator::{either, many, many1, between, many_tail, many1_tail, sep_by, look_ahead, not_followed_by,
+    many_m_n, at_most, at_least, skip_many_m_n, skip_at_least, sep_by1};
+use ruskell::parsec::lexer::{Lexer, Token, token_kind, satisfy_token};
+use ruskell::parsec::lazy::{lazy, forward_declare, ParserRef};
+use ruskell::parsec::iter::parse_iter;
+use ruskell::parsec::text::{uint64, hex_digit};
 use std::sync::Arc;
 use std::iter::FromIterator;
 
@@ -274,7 +279,7 @@ fn many_test_1() {
     let re = many(a).parse(&mut state);
     assert!(re.is_ok());
     let data = re.unwrap();
-    let ver = vec![];
+    let ver:Vec<char> = vec![];
     assert_eq!(data, ver);
 }
 
@@ -417,3 +422,365 @@ fn many1_tail_test_1() {
     let ver = "This is a string.".chars().into_iter().collect::<Vec<char>>();
     assert_eq!(data, ver);
 }
+
+#[test]
+fn seek_to_test_0() {
+    let mut state = VecState::from_iter("abc".chars());
+    assert!(state.seek_to(3));
+    assert_eq!(state.pos(), 3);
+    assert_eq!(state.next(), None);
+    assert!(!state.seek_to(4));
+    assert_eq!(state.pos(), 3);
+    assert!(state.seek_to(0));
+    assert_eq!(state.next(), Some('a'));
+}
+
+fn letter() -> Arc<Parsec<char, char>> {
+    Arc::new(one_of(&"abcdefghijklmnopqrstuvwxyz".chars().collect()))
+}
+
+#[test]
+fn sep_by_test_0() {
+    let mut state = VecState::from_iter("a,b,c".chars());
+    assert_eq!(sep_by(Arc::new(eq(',')), letter()).parse(&mut state).unwrap(), vec!['a', 'b', 'c']);
+    let mut state = VecState::from_iter("".chars());
+    assert!(sep_by(Arc::new(eq(',')), letter()).parse(&mut state).unwrap().is_empty());
+}
+
+#[test]
+fn sep_by_test_1() {
+    // A trailing separator backtracks.
+    let mut state = VecState::from_iter("a,b,".chars());
+    assert_eq!(sep_by(Arc::new(eq(',')), letter()).parse(&mut state).unwrap(), vec!['a', 'b']);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn sep_by1_test_0() {
+    let mut state = VecState::from_iter("a,b,".chars());
+    assert_eq!(sep_by1(Arc::new(eq(',')), letter()).parse(&mut state).unwrap(), vec!['a', 'b']);
+    assert_eq!(state.pos(), 3);
+    let mut state = VecState::from_iter(",a".chars());
+    assert!(sep_by1(Arc::new(eq(',')), letter()).parse(&mut state).is_err());
+}
+
+#[test]
+fn sep_by1_test_1() {
+    // Items need a separator between them.
+    let mut state = VecState::from_iter("ab,c".chars());
+    assert_eq!(sep_by1(Arc::new(eq(',')), letter()).parse(&mut state).unwrap(), vec!['a']);
+    assert_eq!(state.pos(), 1);
+    let mut state = VecState::from_iter("a b".chars());
+    assert_eq!(sep_by(Arc::new(eq(',')), letter()).parse(&mut state).unwrap(), vec!['a']);
+    assert_eq!(state.pos(), 1);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Number,
+    Comma,
+}
+
+fn list_lexer() -> Lexer<Kind> {
+    Lexer::new()
+        .skip(Arc::new(one_of(&vec![' ', '\t', '\n'])))
+        .rule(Kind::Ident, Arc::new(many1(Arc::new(one_of(&"abcdefghijklmnopqrstuvwxyz".chars().collect::<Vec<char>>())))))
+        .rule(Kind::Number, Arc::new(many1(Arc::new(one_of(&"0123456789".chars().collect::<Vec<char>>())))))
+        .rule(Kind::Comma, Arc::new(eq(',')))
+}
+
+#[test]
+fn lexer_test_0() {
+    let tokens = list_lexer().tokenize("abc, 42 ,x").unwrap();
+    let kinds = tokens.iter().map(|t:&Token<Kind>| t.kind.clone()).collect::<Vec<Kind>>();
+    assert_eq!(kinds, vec![Kind::Ident, Kind::Comma, Kind::Number, Kind::Comma, Kind::Ident]);
+    assert_eq!(tokens[2].text, "42");
+    assert_eq!(tokens[2].start, 5);
+    assert_eq!(tokens[2].end, 7);
+}
+
+#[test]
+fn lexer_test_1() {
+    let re = list_lexer().tokenize("abc ; def");
+    assert!(re.is_err());
+    assert_eq!(re.unwrap_err().pos(), 4);
+}
+
+#[test]
+fn token_stream_test_0() {
+    let tokens = list_lexer().tokenize("a, 1, b").unwrap();
+    let mut state:VecState<Token<Kind>> = tokens.into_iter().collect();
+    let item = Arc::new(either(Arc::new(token_kind(Kind::Ident)), Arc::new(token_kind(Kind::Number))));
+    let re = sep_by(Arc::new(token_kind(Kind::Comma)), item).parse(&mut state);
+    let data = re.unwrap();
+    let texts = data.iter().map(|t:&Token<Kind>| t.text.clone()).collect::<Vec<String>>();
+    assert_eq!(texts, vec!["a", "1", "b"]);
+}
+
+#[test]
+fn token_stream_test_1() {
+    let tokens = list_lexer().tokenize("a,   1").unwrap();
+    let mut state:VecState<Token<Kind>> = tokens.into_iter().collect();
+    let p = token_kind(Kind::Ident).then(Arc::new(token_kind(Kind::Comma))).then(Arc::new(token_kind(Kind::Ident)));
+    let re = p.parse(&mut state);
+    assert!(re.is_err());
+    assert_eq!(re.unwrap_err().pos(), 5);
+}
+
+#[test]
+fn token_stream_test_2() {
+    let tokens = list_lexer().tokenize("abc 12").unwrap();
+    let mut state:VecState<Token<Kind>> = tokens.into_iter().collect();
+    let number = satisfy_token(Arc::new(Box::new(|t:&Token<Kind>| t.kind == Kind::Number && t.text.len() > 1)),
+                               String::from("long number"));
+    let p = many(Arc::new(token_kind(Kind::Ident))).then(Arc::new(number)).over(Arc::new(token_kind(Kind::Ident)));
+    let re = p.parse(&mut state);
+    assert!(re.is_err());
+    assert_eq!(re.unwrap_err().pos(), 6);
+}
+
+#[test]
+fn tag_test_0() {
+    let mut state = VecState::from_iter(vec![1, 2, 3, 4].into_iter());
+    let re = tag(&vec![1, 2, 3]).parse(&mut state);
+    assert_eq!(re.unwrap(), vec![1, 2, 3]);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn string_test_0() {
+    let mut state = VecState::from_iter("selection".chars());
+    let re = string("select").parse(&mut state);
+    assert_eq!(re.unwrap(), "select");
+    assert_eq!(state.pos(), 6);
+}
+
+#[test]
+fn string_test_1() {
+    let mut state = VecState::from_iter("selfish".chars());
+    let re = string("select").parse(&mut state);
+    let err = re.unwrap_err();
+    assert_eq!(err.pos(), 0);
+    assert!(err.message().contains("\"select\""));
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn string_test_2() {
+    let mut state = VecState::from_iter("selfish".chars());
+    let p = either(Arc::new(string("select")), Arc::new(string("self")));
+    assert_eq!(p.parse(&mut state).unwrap(), "self");
+}
+
+#[test]
+fn istring_test_0() {
+    let mut state = VecState::from_iter("SeLeCT * FROM ÄPFEL".chars());
+    assert_eq!(istring("select").parse(&mut state).unwrap(), "SeLeCT");
+    let mut state = VecState::from_iter("ÄPFEL".chars());
+    assert_eq!(istring("äpfel").parse(&mut state).unwrap(), "ÄPFEL");
+}
+
+#[test]
+fn istring_test_1() {
+    // Simple case folding maps a char to one char, so 'ß' is not "SS".
+    let mut state = VecState::from_iter("STRASSE".chars());
+    let err = istring("straße").parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 0);
+    assert!(err.message().ends_with("but mismatch"));
+    let mut state = VecState::from_iter("i".chars());
+    assert!(istring("İ").parse(&mut state).is_err());
+    let mut state = VecState::from_iter("İ".chars());
+    assert_eq!(istring("İ").parse(&mut state).unwrap(), "İ");
+}
+
+#[test]
+fn istring_test_2() {
+    let mut state = VecState::from_iter("STRAẞE".chars());
+    assert_eq!(istring("straße").parse(&mut state).unwrap(), "STRAẞE");
+    let mut state = VecState::from_iter("ΟΔΟΣ".chars());
+    assert_eq!(istring("οδος").parse(&mut state).unwrap(), "ΟΔΟΣ");
+    let mut state = VecState::from_iter("\u{212A}m".chars());
+    assert_eq!(istring("KM").parse(&mut state).unwrap(), "\u{212A}m");
+    let mut state = VecState::from_iter("ſ".chars());
+    assert_eq!(istring("S").parse(&mut state).unwrap(), "ſ");
+}
+
+#[test]
+fn one_of_str_test_0() {
+    let mut state = VecState::from_iter("*+x".chars());
+    let p = one_of_str("+-*/");
+    assert_eq!(p.parse(&mut state).unwrap(), '*');
+    assert_eq!(p.parse(&mut state).unwrap(), '+');
+    assert!(p.parse(&mut state).is_err());
+}
+
+// Depth of nested parentheses, the parsec refers to itself by a lazy wrapper.
+fn depth() -> Arc<Parsec<char, usize>> {
+    let nested = between(arc!(eq('(')), arc!(lazy(depth)), arc!(eq(')')));
+    let inc = nested.bind(bnd!(|_:&mut State<char>, d:usize|->Status<usize> { Ok(d + 1) }));
+    arc!(either(arc!(inc), arc!(pack(0))))
+}
+
+#[test]
+fn lazy_test_0() {
+    let mut state = VecState::from_iter("((()))".chars());
+    assert_eq!(depth().parse(&mut state).unwrap(), 3);
+    assert_eq!(state.pos(), 6);
+    let mut state = VecState::from_iter("(()".chars());
+    let err = depth().parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 3);
+}
+
+#[test]
+fn forward_declare_test_0() {
+    // item = 'x' | list, list = '[' item* ']', declared before list is built.
+    let item:ParserRef<char, usize> = forward_declare();
+    let x = eq('x').bind(bnd!(|_:&mut State<char>, _:char|->Status<usize> { Ok(1) }));
+    let items = many(arc!(item.clone())).bind(bnd!(|_:&mut State<char>, xs:Vec<usize>|->Status<usize> {
+        Ok(xs.iter().fold(0, |a, b| a + b))
+    }));
+    let list = between(arc!(eq('[')), arc!(items), arc!(eq(']')));
+    item.set(arc!(either(arc!(x), arc!(list))));
+    assert!(item.is_set());
+    let mut state = VecState::from_iter("[x[x[]x]x]".chars());
+    assert_eq!(item.parse(&mut state).unwrap(), 4);
+    assert_eq!(state.pos(), 10);
+}
+
+#[test]
+fn forward_declare_test_1() {
+    let p:ParserRef<char, char> = forward_declare();
+    let mut state = VecState::from_iter("abc".chars());
+    state.next();
+    let err = p.parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 1);
+    assert!(err.message().contains("before it is defined"));
+    p.set(arc!(one()));
+    assert_eq!(p.parse(&mut state).unwrap(), 'b');
+}
+
+#[test]
+fn look_ahead_test_0() {
+    let mut state = VecState::from_iter("abc".chars());
+    assert_eq!(look_ahead(arc!(string("ab"))).parse(&mut state).unwrap(), "ab");
+    assert_eq!(state.pos(), 0);
+    assert!(look_ahead(arc!(string("abd"))).parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn not_followed_by_test_0() {
+    let mut state = VecState::from_iter("abc".chars());
+    state.next();
+    assert!(not_followed_by(arc!(eq('c'))).parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 1);
+    let err = not_followed_by(arc!(eq('b'))).parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 1);
+    assert_eq!(state.pos(), 1);
+}
+
+fn record() -> Arc<Parsec<char, u64>> {
+    arc!(uint64().over(arc!(eq('\n'))))
+}
+
+#[test]
+fn parse_iter_test_0() {
+    let mut state = VecState::from_iter("12\n34\n".chars());
+    let re:Vec<u64> = parse_iter(record(), &mut state).map(|x:Status<u64>| x.unwrap()).collect();
+    assert_eq!(re, vec![12, 34]);
+    let mut state = VecState::from_iter("".chars());
+    assert!(parse_iter(record(), &mut state).next().is_none());
+}
+
+#[test]
+fn parse_iter_test_1() {
+    let mut state = VecState::from_iter("12\n34\nxx\n56\n".chars());
+    let re:Vec<Status<u64>> = parse_iter(record(), &mut state).collect();
+    assert_eq!(re.len(), 3);
+    assert_eq!(re[2].clone().unwrap_err().pos(), 6);
+    assert_eq!(state.pos(), 6);
+}
+
+#[test]
+fn parse_iter_test_2() {
+    let mut state = VecState::from_iter("12\n34\nxx\n56\n9".chars());
+    let line = many(arc!(ne('\n'))).then(arc!(eq('\n')));
+    let re:Vec<Status<u64>> = parse_iter(record(), &mut state).resync(arc!(line)).collect();
+    assert_eq!(re.len(), 5);
+    assert_eq!(re[1].clone().unwrap(), 34);
+    assert_eq!(re[2].clone().unwrap_err().pos(), 6);
+    assert_eq!(re[3].clone().unwrap(), 56);
+    // The last record has no newline and nothing to resync.
+    assert!(re[4].is_err());
+}
+
+#[test]
+fn many_m_n_test_0() {
+    let p = many_m_n(2, 4, arc!(hex_digit()));
+    let mut state = VecState::from_iter("1aF3e".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), vec!['1', 'a', 'F', '3']);
+    assert_eq!(state.pos(), 4);
+    let mut state = VecState::from_iter("ab".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), vec!['a', 'b']);
+}
+
+#[test]
+fn many_m_n_test_1() {
+    let p = many_m_n(2, 4, arc!(hex_digit()));
+    let mut state = VecState::from_iter("1x".chars());
+    let err = p.parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 1);
+    assert!(err.message().starts_with("expect 2 to 4 items but got 1 at 1"));
+}
+
+#[test]
+fn at_most_test_0() {
+    let mut state = VecState::from_iter("aaab".chars());
+    assert_eq!(at_most(2, arc!(eq('a'))).parse(&mut state).unwrap(), vec!['a', 'a']);
+    let mut state = VecState::from_iter("b".chars());
+    assert!(at_most(2, arc!(eq('a'))).parse(&mut state).unwrap().is_empty());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn at_least_test_0() {
+    let mut state = VecState::from_iter("aaab".chars());
+    assert_eq!(at_least(2, arc!(eq('a'))).parse(&mut state).unwrap(), vec!['a', 'a', 'a']);
+    let mut state = VecState::from_iter("ab".chars());
+    let err = at_least(2, arc!(eq('a'))).parse(&mut state).unwrap_err();
+    assert!(err.message().starts_with("expect at least 2 items but got 1 at 1"));
+}
+
+#[test]
+fn skip_many_m_n_test_0() {
+    let mut state = VecState::from_iter("aaa".chars());
+    assert!(skip_many_m_n(1, 2, arc!(eq('a'))).parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 2);
+    let mut state = VecState::from_iter("a".chars());
+    assert!(skip_at_least(2, arc!(eq('a'))).parse(&mut state).is_err());
+    // An item consume nothing stops the repetition.
+    let mut state = VecState::from_iter("a".chars());
+    assert!(skip_at_least(1, arc!(pack::<char, ()>(()))).parse(&mut state).is_ok());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn peek_expect_scan_test_0() {
+    let mut state = VecState::from_iter("ab".chars());
+    assert_eq!(peek(&mut state), Some('a'));
+    assert_eq!(state.pos(), 0);
+    let err = expect(&mut state, "digit");
+    assert_eq!(err.pos(), 0);
+    assert_eq!(err.message(), "expect digit at 0 but got 'a'");
+    let rest = scan(Arc::new(Box::new(|state:&mut State<char>|->Status<String> {
+        let mut re = String::new();
+        while let Some(c) = state.next() {
+            re.push(c);
+        }
+        Ok(re)
+    })));
+    assert_eq!(rest.parse(&mut state).unwrap(), "ab");
+    assert_eq!(peek(&mut state), None);
+    assert_eq!(expect(&mut state, "digit").message(), "expect digit at 2 but got eof");
+}
@@ -0,0 +1,73 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, Error, Parsec, M};
+use ruskell::parsec::atom::eq;
+use ruskell::parsec::text::uint64;
+use ruskell::parsec::parallel::{split_chunks, parse_chunks_parallel};
+use std::iter::FromIterator;
+use std::sync::Arc;
+use std::thread;
+
+fn record() -> Arc<Parsec<char, u64>> {
+    arc!(uint64().over(arc!(eq('\n'))))
+}
+
+fn lines(count:u64) -> String {
+    (0..count).map(|i:u64| format!("{}\n", i * 7)).collect()
+}
+
+#[test]
+fn share_test_0() {
+    let p = record();
+    let workers:Vec<thread::JoinHandle<u64>> = (0..4).map(|i:u64| {
+        let p = p.clone();
+        thread::spawn(move || {
+            let mut state = VecState::from_iter(format!("{}\n", i).chars());
+            p.parse(&mut state).unwrap()
+        })
+    }).collect();
+    let re:Vec<u64> = workers.into_iter().map(|w:thread::JoinHandle<u64>| w.join().unwrap()).collect();
+    assert_eq!(re, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn split_chunks_test_0() {
+    let text = lines(50);
+    let chunks = split_chunks(text.as_str(), "\n", 4);
+    assert_eq!(chunks.len(), 4);
+    let mut offset = 0;
+    for &(start, ref chunk) in chunks.iter() {
+        assert_eq!(start, offset);
+        assert!(chunk.ends_with("\n"));
+        offset += chunk.chars().count();
+    }
+    let joined:String = chunks.iter().map(|&(_, ref chunk):&(usize, String)| chunk.clone()).collect();
+    assert_eq!(joined, text);
+    assert_eq!(split_chunks("a\n\nb\n\nc", "\n\n", 8).len(), 3);
+}
+
+#[test]
+fn parse_chunks_parallel_test_0() {
+    let text = lines(200);
+    let re = parse_chunks_parallel(record(), text.as_str(), "\n", 4).unwrap();
+    assert_eq!(re, (0..200).map(|i:u64| i * 7).collect::<Vec<u64>>());
+    assert_eq!(parse_chunks_parallel(record(), "", "\n", 4).unwrap(), Vec::<u64>::new());
+}
+
+#[test]
+fn parse_chunks_parallel_test_1() {
+    let mut text = lines(100);
+    text.push_str(lines(100).replace("350\n", "35x\n").as_str());
+    let bad = text.find('x').unwrap();
+    let errors = parse_chunks_parallel(record(), text.as_str(), "\n", 3).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pos(), bad);
+    // The same error as parsing the whole input at once.
+    let mut state = VecState::from_iter(text.chars());
+    let p = record();
+    let mut re = p.parse(&mut state);
+    while re.is_ok() {
+        re = p.parse(&mut state);
+    }
+    assert_eq!(re.unwrap_err().pos(), errors[0].pos());
+}
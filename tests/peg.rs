@@ -0,0 +1,88 @@
+extern crate ruskell;
+use ruskell::parsec::Error;
+use ruskell::parsec::peg::{Peg, Expr, read};
+
+const ARITH:&'static str = "
+# sums of numbers
+sum    <- number (op number)*
+op     <- '+' / '-'
+number <- [0-9]+ / '(' sum ')'
+";
+
+#[test]
+fn peg_test_0() {
+    let peg = Peg::new(ARITH).unwrap();
+    assert_eq!(peg.start(), "sum");
+    let tree = peg.parse("1+(2-3)").unwrap();
+    assert_eq!(format!("{}", tree),
+               "sum[0, 7](number[0, 1] op[1, 2] number[2, 7](sum[3, 6](number[3, 4] op[4, 5] number[5, 6])))");
+    assert_eq!(tree.children[2].text("1+(2-3)"), "(2-3)");
+    let err = peg.parse("1+").unwrap_err();
+    assert_eq!(err.pos(), 1);
+}
+
+#[test]
+fn peg_test_1() {
+    let peg = Peg::new("list = item (',' item)* ;\nitem = [a-z]+ | '[' list ']' ;").unwrap();
+    let tree = peg.parse("a,[b,c]").unwrap();
+    assert_eq!(format!("{}", tree), "list[0, 7](item[0, 1] item[2, 7](list[3, 6](item[3, 4] item[5, 6])))");
+    assert!(peg.parse("a,[b").is_err());
+}
+
+#[test]
+fn peg_test_2() {
+    let peg = Peg::new("word <- !keyword [a-z]+\nkeyword <- ('if' / 'else') ![a-z]").unwrap();
+    assert_eq!(format!("{}", peg.parse("iffy").unwrap()), "word[0, 4]");
+    assert!(peg.parse("if").is_err());
+    assert_eq!(format!("{}", peg.parse_rule("keyword", "else").unwrap()), "keyword[0, 4]");
+    assert!(peg.parse_rule("value", "else").is_err());
+}
+
+#[test]
+fn peg_test_3() {
+    let definitions = read("s <- 'a'? [^\"\\]] . &'b'").unwrap();
+    assert_eq!(definitions.len(), 1);
+    match definitions[0].expr {
+        Expr::Sequence(ref items) => {
+            assert_eq!(items.len(), 4);
+            assert_eq!(items[3], Expr::And(Box::new(Expr::Literal(String::from("b")))));
+        }
+        ref e => panic!("expect sequence but got {:?}", e),
+    }
+}
+
+#[test]
+fn peg_error_test_0() {
+    let err = Peg::new("s <- a b\na <- 'x'").unwrap_err();
+    assert_eq!(err, "undefined rule b at 7");
+    let err = Peg::new("s <- 'x'\ns <- 'y'").unwrap_err();
+    assert_eq!(err, "rule s at 9 is already defined at 0");
+}
+
+#[test]
+fn peg_error_test_1() {
+    let err = Peg::new("a <- 'a' / 'ab'").unwrap_err();
+    assert_eq!(err, "alternative at 11 is unreachable, \"a\" at 5 matches its prefix");
+    let err = Peg::new("s <- x / 'y'\nx <- 'x'*").unwrap_err();
+    assert_eq!(err, "alternative at 9 is unreachable, alternative at 5 always matches");
+    let err = Peg::new("s <- ('a'?)*").unwrap_err();
+    assert_eq!(err, "repetition in rule s may match empty input forever");
+}
+
+#[test]
+fn peg_error_test_3() {
+    let err = Peg::new("a <- a 'x'").unwrap_err();
+    assert_eq!(err, "left recursive rule a at 0");
+    let err = Peg::new("s <- 'y'\na <- b 'x' / 'z'\nb <- 'q'? a").unwrap_err();
+    assert_eq!(err, "left recursive rule a at 9");
+    assert!(Peg::new("a <- 'x' a / 'y'").is_ok());
+}
+
+#[test]
+fn peg_error_test_2() {
+    let err = read("s <- 'abc").unwrap_err();
+    assert_eq!(err.pos(), 5);
+    assert!(err.message().contains("unterminated"));
+    let err = Peg::new("s <- [z-a]").unwrap_err();
+    assert!(err.starts_with("invalid grammar"));
+}
@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Status, Error, M};
+use ruskell::parsec::atom::eof;
+use ruskell::parsec::combinator::many1;
+use ruskell::parsec::text::{uint64, alpha};
+use ruskell::parsec::lexeme::{lexeme, symbol};
+use ruskell::parsec::perm::{PermItem, perm_item, perm_default, perm_option, permutation, perm2, perm3};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+fn size() -> Arc<Parsec<char, u64>> {
+    arc!(symbol("size").then(arc!(symbol("="))).then(arc!(lexeme(arc!(uint64())))))
+}
+
+fn color() -> Arc<Parsec<char, String>> {
+    let name = lexeme(arc!(many1(arc!(alpha()))));
+    arc!(symbol("color").then(arc!(symbol("="))).then(arc!(name))
+        .bind(bnd!(|_:&mut State<char>, cs:Vec<char>|->Status<String> { Ok(cs.into_iter().collect()) })))
+}
+
+fn options() -> Arc<Parsec<char, (u64, String, Option<String>)>> {
+    let verbose = perm_option(arc!(symbol("verbose")));
+    arc!(perm3(perm_item(size()), perm_item(color()), verbose).over(arc!(eof())))
+}
+
+#[test]
+fn perm_test_0() {
+    let mut state = VecState::from_iter("color=red size=3".chars());
+    assert_eq!(options().parse(&mut state).unwrap(), (3, String::from("red"), None));
+    let mut state = VecState::from_iter("verbose size = 3 color=red".chars());
+    assert_eq!(options().parse(&mut state).unwrap(), (3, String::from("red"), Some(String::from("verbose"))));
+}
+
+#[test]
+fn perm_test_1() {
+    // A missing required item fails after the items found.
+    let mut state = VecState::from_iter("verbose color=red".chars());
+    let re = options().parse(&mut state);
+    assert!(re.is_err());
+    assert_eq!(re.unwrap_err().pos(), 17);
+}
+
+#[test]
+fn perm_test_2() {
+    // Every item is taken once, the second size is left.
+    let p = perm2(perm_item(size()), perm_default(color(), String::from("black")));
+    let mut state = VecState::from_iter("size=1 size=2".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), (1, String::from("black")));
+    assert_eq!(state.pos(), 7);
+}
+
+#[test]
+fn permutation_test_0() {
+    let flag = |name:&str| -> PermItem<char, String> {
+        perm_default(arc!(symbol(name)), String::new())
+    };
+    let p = permutation(vec![flag("-a"), flag("-b"), flag("-c")]).over(arc!(eof()));
+    let mut state = VecState::from_iter("-c -a".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), vec!["-a", "", "-c"]);
+    let mut state = VecState::from_iter("-c -a -c".chars());
+    assert!(p.parse(&mut state).is_err());
+}
@@ -0,0 +1,51 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Error};
+use ruskell::parsec::combinator::either;
+use ruskell::parsec::regex::{RegexParsec, regex};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+#[test]
+fn regex_test_0() {
+    let mut state = VecState::from_iter("2015-06-01T12:30:00Z rest".chars());
+    let p = regex(r"(\d{4})-(\d{2})-(\d{2})T\d{2}:\d{2}:\d{2}Z");
+    let m = p.parse(&mut state).unwrap();
+    assert_eq!(m.text, "2015-06-01T12:30:00Z");
+    assert_eq!(m.group(1), Some("2015"));
+    assert_eq!(m.group(3), Some("01"));
+    assert_eq!(state.pos(), 20);
+}
+
+#[test]
+fn regex_test_1() {
+    let mut state = VecState::from_iter("- 中文id".chars());
+    let word = regex(r"\w+");
+    assert!(word.parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+    let p = either(Arc::new(word.clone()), Arc::new(regex("- ")));
+    assert_eq!(p.parse(&mut state).unwrap().text, "- ");
+    assert_eq!(word.parse(&mut state).unwrap().text, "中文id");
+    assert_eq!(state.pos(), 6);
+}
+
+#[test]
+fn regex_test_2() {
+    let mut state = VecState::from_iter("abc".chars());
+    let re = regex("b").parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 0);
+    assert!(RegexParsec::new("(").is_err());
+}
+
+#[test]
+fn regex_window_test_0() {
+    // A match reaching the end of window is tried again in a longer window.
+    let mut state = VecState::from_iter("abcdefghij!".chars());
+    let p = RegexParsec::new("[a-z]+").unwrap().window(3);
+    assert_eq!(p.parse(&mut state).unwrap().text, "abcdefghij");
+    assert_eq!(state.pos(), 10);
+    let mut state = VecState::from_iter("abc".chars());
+    assert!(RegexParsec::new("ab$").unwrap().window(2).parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+    let mut state = VecState::from_iter("ab".chars());
+    assert_eq!(RegexParsec::new("ab$").unwrap().window(2).parse(&mut state).unwrap().end, 2);
+}
@@ -0,0 +1,68 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{State, VecState, Parsec, Status, M};
+use ruskell::parsec::atom::{eq, pack, string};
+use ruskell::parsec::combinator::many1;
+use ruskell::parsec::text::{uint64, space};
+use ruskell::parsec::cst::Spanned;
+use ruskell::parsec::search::{find_first, find_all, split_by, replace_all};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+fn ipv4() -> Arc<Parsec<char, Vec<u64>>> {
+    let octet = arc!(uint64());
+    let dot = arc!(eq('.'));
+    arc!(pack(()).bind(bnd!(move |state:&mut State<char>, _:()|->Status<Vec<u64>> {
+        let mut re = vec![try!(octet.parse(state))];
+        for _ in 0..3 {
+            try!(dot.parse(state));
+            re.push(try!(octet.parse(state)));
+        }
+        Ok(re)
+    })))
+}
+
+#[test]
+fn find_first_test_0() {
+    let mut state = VecState::from_iter("host 1.2 at 10.0.0.1 up".chars());
+    let found = find_first(&*ipv4(), &mut state).unwrap();
+    assert_eq!(found, Spanned{value:vec![10, 0, 0, 1], start:12, end:20});
+    assert_eq!(state.pos(), 20);
+    assert!(find_first(&*ipv4(), &mut state).is_none());
+}
+
+#[test]
+fn find_all_test_0() {
+    let mut state = VecState::from_iter("a 1.1.1.1, b 192.168.0.7;c 8.8.8.8".chars());
+    let re:Vec<(usize, usize)> = find_all(&*ipv4(), &mut state).iter()
+        .map(|m:&Spanned<Vec<u64>>| (m.start, m.end)).collect();
+    assert_eq!(re, vec![(2, 9), (13, 24), (27, 34)]);
+    let mut state = VecState::from_iter("no address".chars());
+    assert!(find_all(&*ipv4(), &mut state).is_empty());
+}
+
+#[test]
+fn find_all_test_1() {
+    // Empty matches don't stop the search.
+    let mut state = VecState::from_iter("ab".chars());
+    let re = find_all(&pack::<char, ()>(()), &mut state);
+    assert_eq!(re.iter().map(|m:&Spanned<()>| m.start).collect::<Vec<usize>>(), vec![0, 1, 2]);
+}
+
+#[test]
+fn split_by_test_0() {
+    let re = split_by(&many1(arc!(space())), " a  b\tc ");
+    assert_eq!(re, vec!["", "a", "b", "c", ""]);
+    assert_eq!(split_by(&string("::"), "a::b:c"), vec!["a", "b:c"]);
+    assert_eq!(split_by(&string("::"), ""), vec![""]);
+}
+
+#[test]
+fn replace_all_test_0() {
+    let re = replace_all(&*ipv4(), "from 10.0.0.1 to 10.0.0.2", |m:&Spanned<Vec<u64>>| {
+        format!("<{}>", m.value[3])
+    });
+    assert_eq!(re, "from <1> to <2>");
+    let re = replace_all(&string("é"), "café éclair", |_:&Spanned<String>| String::from("e"));
+    assert_eq!(re, "cafe eclair");
+}
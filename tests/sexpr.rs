@@ -0,0 +1,81 @@
+extern crate ruskell;
+use ruskell::formats::sexpr::{Sexpr, Node, SexprReader, read, read_all};
+use ruskell::parsec::{VecState, State, Parsec, Error};
+use std::iter::FromIterator;
+
+fn symbol(name:&str, start:usize) -> Node {
+    Node::new(Sexpr::Symbol(String::from(name)), start, start + name.len())
+}
+
+#[test]
+fn sexpr_atom_test_0() {
+    assert_eq!(read("foo-bar?").unwrap(), symbol("foo-bar?", 0));
+    assert_eq!(read(" 42 ").unwrap(), Node::new(Sexpr::Integer(42), 1, 3));
+    assert_eq!(read("-0x1f").unwrap().value, Sexpr::Integer(-31));
+    assert_eq!(read("2.5e3").unwrap().value, Sexpr::Float(2500.0));
+    assert_eq!(read(".5").unwrap().value, Sexpr::Float(0.5));
+    assert_eq!(read("1+").unwrap().value, Sexpr::Symbol(String::from("1+")));
+    assert_eq!(read("-").unwrap().value, Sexpr::Symbol(String::from("-")));
+    assert_eq!(read("\"a\\\"b\\n\"").unwrap(), Node::new(Sexpr::Str(String::from("a\"b\n")), 0, 8));
+}
+
+#[test]
+fn sexpr_list_test_0() {
+    let node = read("(define (f x) ; comment\n  '(x . 1))").unwrap();
+    assert_eq!((node.start, node.end), (0, 35));
+    let items = node.as_list().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0], symbol("define", 1));
+    assert_eq!(items[1].as_list().map(|items:&Vec<Node>| items.len()), Some(2));
+    assert_eq!((items[1].start, items[1].end), (8, 13));
+    let pair = Node::new(Sexpr::Dotted(vec![symbol("x", 28)], Box::new(Node::new(Sexpr::Integer(1), 32, 33))), 27, 34);
+    assert_eq!(items[2], Node::new(Sexpr::Quote(Box::new(pair)), 26, 34));
+    assert_eq!(format!("{}", node), "(define (f x) '(x . 1))");
+}
+
+#[test]
+fn sexpr_comment_test_0() {
+    let nodes = read_all("#| block #| nested |# |# (a)\n; line\n(b #|inner|# c) 1.0").unwrap();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(format!("{}", nodes[1]), "(b c)");
+    assert_eq!(format!("{}", nodes[2]), "1.0");
+    assert_eq!(read("()").unwrap().value, Sexpr::List(Vec::new()));
+}
+
+#[test]
+fn sexpr_reader_test_0() {
+    let mut state = VecState::from_iter("(a b) rest".chars());
+    let reader = SexprReader::new();
+    assert_eq!(format!("{}", reader.parse(&mut state).unwrap()), "(a b)");
+    assert_eq!(state.pos(), 6);
+}
+
+#[test]
+fn sexpr_error_test_0() {
+    let err = read("(a \"open)").unwrap_err();
+    assert_eq!(err.pos(), 3);
+    let err = read("(a (b)").unwrap_err();
+    assert_eq!(err.pos(), 6);
+    let err = read("(. a)").unwrap_err();
+    assert_eq!(err.pos(), 1);
+    let err = read("(a . b c)").unwrap_err();
+    assert_eq!(err.pos(), 7);
+    let err = read("a)").unwrap_err();
+    assert_eq!(err.pos(), 1);
+    let err = read("(1 99999999999999999999)").unwrap_err();
+    assert_eq!(err.pos(), 3);
+}
+
+#[test]
+fn sexpr_depth_test_0() {
+    let deep = format!("{}{}", (0..10).map(|_| "(").collect::<String>(), (0..10).map(|_| ")").collect::<String>());
+    let mut state = VecState::from_iter(deep.chars());
+    assert!(SexprReader::new().max_depth(10).parse(&mut state).is_ok());
+    let mut state = VecState::from_iter(deep.chars());
+    let err = SexprReader::new().max_depth(9).parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 9);
+    assert!(err.message().starts_with("sexpr nested deeper than 9"));
+    // Far deeper than the default is an error, not a stack overflow.
+    let deep = (0..100000).map(|_| "('").collect::<String>();
+    assert_eq!(read(deep.as_str()).unwrap_err().pos(), 256);
+}
@@ -0,0 +1,217 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Error, M};
+use ruskell::parsec::atom::one_of;
+use ruskell::parsec::text::{string_literal, char_literal, int32, int64, uint64, float64};
+use ruskell::parsec::text::{hex_digit, oct_digit, upper, lower, punctuation};
+use std::iter::FromIterator;
+use std::sync::Arc;
+use std::{i32, i64, u64, f64};
+
+#[test]
+fn string_literal_test_0() {
+    let mut state = VecState::from_iter("\"a\\tb\\n\\\\\\\"c\" tail".chars());
+    let re = string_literal('"').parse(&mut state);
+    assert_eq!(re.unwrap(), "a\tb\n\\\"c");
+    assert_eq!(state.pos(), 13);
+}
+
+#[test]
+fn string_literal_test_1() {
+    let mut state = VecState::from_iter("'\\u{48}\\x69\\u{1F600}'".chars());
+    let re = string_literal('\'').parse(&mut state);
+    assert_eq!(re.unwrap(), "Hi\u{1F600}");
+}
+
+#[test]
+fn string_literal_test_2() {
+    let mut state = VecState::from_iter("\"abc\\qdef\"".chars());
+    let re = string_literal('"').parse(&mut state);
+    let err = re.unwrap_err();
+    assert_eq!(err.pos(), 4);
+}
+
+#[test]
+fn string_literal_test_3() {
+    let mut state = VecState::from_iter("\"ok\\u{110000}\"".chars());
+    let re = string_literal('"').parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 3);
+}
+
+#[test]
+fn string_literal_test_4() {
+    let mut state = VecState::from_iter("\"never closed".chars());
+    let re = string_literal('"').parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 0);
+}
+
+#[test]
+fn raw_string_literal_test_0() {
+    let mut state = VecState::from_iter("r#\"a \"quoted\" \\n\"#".chars());
+    let re = string_literal('"').parse(&mut state);
+    assert_eq!(re.unwrap(), "a \"quoted\" \\n");
+}
+
+#[test]
+fn raw_string_literal_test_1() {
+    let mut state = VecState::from_iter("r\"plain\\\"".chars());
+    let re = string_literal('"').parse(&mut state);
+    assert_eq!(re.unwrap(), "plain\\");
+}
+
+#[test]
+fn string_literal_test_5() {
+    // Input not starting a literal is left for the next alternative.
+    let mut state = VecState::from_iter("abc".chars());
+    let err = string_literal('"').parse(&mut state).unwrap_err();
+    assert_eq!(err.pos(), 0);
+    assert_eq!(state.pos(), 0);
+    let mut state = VecState::from_iter("rx".chars());
+    assert!(string_literal('"').parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn char_literal_test_0() {
+    let mut state = VecState::from_iter("'a''\\n''\\''".chars());
+    let p = char_literal();
+    assert_eq!(p.parse(&mut state).unwrap(), 'a');
+    assert_eq!(p.parse(&mut state).unwrap(), '\n');
+    assert_eq!(p.parse(&mut state).unwrap(), '\'');
+}
+
+#[test]
+fn char_literal_test_1() {
+    let mut state = VecState::from_iter("'ab'".chars());
+    let re = char_literal().parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 0);
+    let mut state = VecState::from_iter("abc".chars());
+    assert_eq!(char_literal().parse(&mut state).unwrap_err().pos(), 0);
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn int64_test_0() {
+    let mut state = VecState::from_iter("-1_000 0x_ff".chars());
+    let p = int64();
+    let space = one_of(&vec![' ']);
+    assert_eq!(p.parse(&mut state).unwrap(), -1000);
+    space.parse(&mut state).unwrap();
+    let re = p.parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 9);
+    assert_eq!(state.pos(), 7);
+}
+
+#[test]
+fn int64_test_1() {
+    let mut state = VecState::from_iter("0xff 0b101 +0o17 -9223372036854775808".chars());
+    let p = int64();
+    let space = one_of(&vec![' ']);
+    assert_eq!(p.parse(&mut state).unwrap(), 255);
+    space.parse(&mut state).unwrap();
+    assert_eq!(p.parse(&mut state).unwrap(), 5);
+    space.parse(&mut state).unwrap();
+    assert_eq!(p.parse(&mut state).unwrap(), 15);
+    space.parse(&mut state).unwrap();
+    assert_eq!(p.parse(&mut state).unwrap(), i64::MIN);
+}
+
+#[test]
+fn int64_test_2() {
+    let mut state = VecState::from_iter("x 9223372036854775808".chars());
+    assert!(one_of(&vec!['x']).then(Arc::new(one_of(&vec![' ']))).parse(&mut state).is_ok());
+    let re = int64().parse(&mut state);
+    assert_eq!(re.unwrap_err().pos(), 2);
+}
+
+#[test]
+fn int64_test_3() {
+    let mut state = VecState::from_iter("abc".chars());
+    let re = int64().parse(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn int32_test_0() {
+    let mut state = VecState::from_iter("-2147483648".chars());
+    assert_eq!(int32().parse(&mut state).unwrap(), i32::MIN);
+    let mut state = VecState::from_iter("2147483648".chars());
+    assert_eq!(int32().parse(&mut state).unwrap_err().pos(), 0);
+}
+
+#[test]
+fn uint64_test_0() {
+    let mut state = VecState::from_iter("18446744073709551615".chars());
+    assert_eq!(uint64().parse(&mut state).unwrap(), u64::MAX);
+    let mut state = VecState::from_iter("18446744073709551616".chars());
+    assert!(uint64().parse(&mut state).is_err());
+}
+
+#[test]
+fn int64_test_4() {
+    // Every `_` needs a digit on both sides, the number stops before a bad one.
+    let p = int64();
+    let mut state = VecState::from_iter("1_2".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), 12);
+    let mut state = VecState::from_iter("1__2".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), 1);
+    assert_eq!(state.pos(), 1);
+    let mut state = VecState::from_iter("12_".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), 12);
+    assert_eq!(state.pos(), 2);
+    let mut state = VecState::from_iter("0b1_".chars());
+    assert_eq!(p.parse(&mut state).unwrap(), 1);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn float64_test_0() {
+    let mut state = VecState::from_iter("-1_000.25e-2".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), -10.0025);
+    let mut state = VecState::from_iter("6.02E23".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), 6.02e23);
+    let mut state = VecState::from_iter(".5".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), 0.5);
+}
+
+#[test]
+fn float64_test_1() {
+    let mut state = VecState::from_iter("-inf".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), f64::NEG_INFINITY);
+    let mut state = VecState::from_iter("NaN".chars());
+    assert!(float64().parse(&mut state).unwrap().is_nan());
+}
+
+#[test]
+fn float64_test_2() {
+    let mut state = VecState::from_iter("12else".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), 12.0);
+    assert_eq!(state.pos(), 2);
+    let mut state = VecState::from_iter("1e999".chars());
+    assert_eq!(float64().parse(&mut state).unwrap_err().pos(), 0);
+}
+
+#[test]
+fn float64_test_3() {
+    // inf and nan are whole words.
+    let mut state = VecState::from_iter("information".chars());
+    assert!(float64().parse(&mut state).is_err());
+    let mut state = VecState::from_iter("nancy".chars());
+    assert!(float64().parse(&mut state).is_err());
+    let mut state = VecState::from_iter("infinity)".chars());
+    assert_eq!(float64().parse(&mut state).unwrap(), f64::INFINITY);
+    assert_eq!(state.pos(), 8);
+}
+
+#[test]
+fn char_class_text_test_0() {
+    let mut state = VecState::from_iter("fF7!x".chars());
+    assert_eq!(hex_digit().parse(&mut state).unwrap(), 'f');
+    assert_eq!(upper().parse(&mut state).unwrap(), 'F');
+    assert_eq!(oct_digit().parse(&mut state).unwrap(), '7');
+    assert_eq!(punctuation().parse(&mut state).unwrap(), '!');
+    assert!(upper().parse(&mut state).is_err());
+    assert_eq!(lower().parse(&mut state).unwrap(), 'x');
+}
+